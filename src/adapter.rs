@@ -14,16 +14,19 @@
 
 //! Adapter for a specific service.
 
-use futures::Future;
+use std::time::Duration;
+
+use futures::{Future, IntoFuture};
 use reqwest::r#async::{RequestBuilder, Response};
 use reqwest::{Method, Url};
 use serde::de::DeserializeOwned;
 use serde::Serialize;
 
 use super::config;
-use super::request;
 use super::services::ServiceType;
-use super::{ApiVersion, AuthType, Error, Session};
+use super::{
+    ApiVersion, AuthType, EndpointFilter, Error, ProxyConfig, RewriteRules, Session, TlsConfig,
+};
 
 /// Adapter for a specific service.
 ///
@@ -44,31 +47,49 @@ impl<Srv> From<Adapter<Srv>> for Session {
 
 impl<Srv> Adapter<Srv> {
     /// Create a new adapter with a given authentication plugin.
-    pub fn new<Auth: AuthType + 'static>(auth_type: Auth, service: Srv) -> Adapter<Srv> {
+    pub fn new<Auth: AuthType + 'static>(auth_type: Auth, service: Srv) -> Adapter<Srv>
+    where
+        Srv: ServiceType,
+    {
         Adapter::from_session(Session::new(auth_type), service)
     }
 
     /// Create a new adapter from a `clouds.yaml` configuration file.
     #[inline]
-    pub fn from_config<S: AsRef<str>>(cloud_name: S, service: Srv) -> Result<Adapter<Srv>, Error> {
+    pub fn from_config<S: AsRef<str>>(cloud_name: S, service: Srv) -> Result<Adapter<Srv>, Error>
+    where
+        Srv: ServiceType,
+    {
         Ok(config::from_config(cloud_name)?.into_adapter(service))
     }
 
     /// Create a new adapter with information from environment variables.
     ///
-    /// Uses some of `OS_*` variables recognized by `python-openstackclient`.
+    /// Uses `OS_CLOUD` or the `OS_*` variables recognized by `python-openstackclient`, see
+    /// [from_env](fn.from_env.html) for details.
     #[inline]
-    pub fn from_env(service: Srv) -> Result<Adapter<Srv>, Error> {
+    pub fn from_env(service: Srv) -> Result<Adapter<Srv>, Error>
+    where
+        Srv: ServiceType,
+    {
         Ok(config::from_env()?.into_adapter(service))
     }
 
     /// Create a new adapter from a `Session`.
-    #[inline]
-    pub fn from_session(session: Session, service: Srv) -> Adapter<Srv> {
+    ///
+    /// The default API version is taken from the
+    /// [service options](struct.Session.html#method.service_options) of the session (if any).
+    pub fn from_session(session: Session, service: Srv) -> Adapter<Srv>
+    where
+        Srv: ServiceType,
+    {
+        let default_api_version = session
+            .service_options(service.catalog_type())
+            .and_then(|options| options.default_api_version());
         Adapter {
             inner: session,
             service,
-            default_api_version: None,
+            default_api_version,
         }
     }
 
@@ -90,6 +111,36 @@ impl<Srv> Adapter<Srv> {
         self.inner.endpoint_interface()
     }
 
+    /// Endpoint filter in use.
+    #[inline]
+    pub fn endpoint_filter(&self) -> &EndpointFilter {
+        self.inner.endpoint_filter()
+    }
+
+    /// Region in use (if any).
+    #[inline]
+    pub fn region(&self) -> Option<String> {
+        self.inner.region()
+    }
+
+    /// Rules used to rewrite endpoint URLs.
+    #[inline]
+    pub fn rewrite_rules(&self) -> &RewriteRules {
+        self.inner.rewrite_rules()
+    }
+
+    /// Time before a failed endpoint is tried again.
+    #[inline]
+    pub fn failover_cooldown(&self) -> Duration {
+        self.inner.failover_cooldown()
+    }
+
+    /// Time after which cached service information expires (if any).
+    #[inline]
+    pub fn service_info_ttl(&self) -> Option<Duration> {
+        self.inner.service_info_ttl()
+    }
+
     /// Update the authentication and purges cached endpoint information.
     ///
     /// # Warning
@@ -125,10 +176,28 @@ impl<Srv> Adapter<Srv> {
         self.default_api_version = api_version;
     }
 
+    /// Use the given proxy options for authentication and service requests.
+    ///
+    /// See [Session::set_proxy](struct.Session.html#method.set_proxy) for details.
+    #[inline]
+    pub fn set_proxy(&mut self, proxy: ProxyConfig) -> Result<(), Error> {
+        self.inner.set_proxy(proxy)
+    }
+
+    /// Use the given TLS options for authentication and service requests.
+    ///
+    /// See [Session::set_tls_config](struct.Session.html#method.set_tls_config) for details.
+    #[inline]
+    pub fn set_tls_config(&mut self, tls: TlsConfig) -> Result<(), Error> {
+        self.inner.set_tls_config(tls)
+    }
+
     /// Set endpoint interface to use.
     ///
-    /// This call clears the cached service information for this `Adapter`.
-    /// It does not, however, affect clones of this `Adapter`.
+    /// The interface takes precedence over the one from the service options, e.g. from the
+    /// `<service>_interface` key in `clouds.yaml`.
+    ///
+    /// Service information is cached per interface, so this call does not clear the cache.
     pub fn set_endpoint_interface<S>(&mut self, endpoint_interface: S)
     where
         S: Into<String>,
@@ -136,6 +205,47 @@ impl<Srv> Adapter<Srv> {
         self.inner.set_endpoint_interface(endpoint_interface);
     }
 
+    /// Set the filter used to select endpoints from the service catalog.
+    ///
+    /// This call clears the cached service information for this `Adapter`.
+    /// It does not, however, affect clones of this `Adapter`.
+    #[inline]
+    pub fn set_endpoint_filter(&mut self, filter: EndpointFilter) {
+        self.inner.set_endpoint_filter(filter);
+    }
+
+    /// Set the region to use.
+    ///
+    /// See [Session::set_region](struct.Session.html#method.set_region) for details.
+    #[inline]
+    pub fn set_region<S>(&mut self, region: S)
+    where
+        S: Into<String>,
+    {
+        self.inner.set_region(region);
+    }
+
+    /// Set the rules used to rewrite endpoint URLs.
+    ///
+    /// This call clears the cached service information for this `Adapter`.
+    /// It does not, however, affect clones of this `Adapter`.
+    #[inline]
+    pub fn set_rewrite_rules(&mut self, rules: RewriteRules) {
+        self.inner.set_rewrite_rules(rules);
+    }
+
+    /// Set the time before a failed endpoint is tried again.
+    #[inline]
+    pub fn set_failover_cooldown(&mut self, cooldown: Duration) {
+        self.inner.set_failover_cooldown(cooldown);
+    }
+
+    /// Set the time after which cached service information expires.
+    #[inline]
+    pub fn set_service_info_ttl(&mut self, ttl: Option<Duration>) {
+        self.inner.set_service_info_ttl(ttl);
+    }
+
     /// Convert this adapter into one using the given authentication.
     #[inline]
     pub fn with_auth_type<Auth: AuthType + 'static>(mut self, auth_method: Auth) -> Adapter<Srv> {
@@ -150,6 +260,20 @@ impl<Srv> Adapter<Srv> {
         self
     }
 
+    /// Convert this adapter into one using the given proxy options.
+    #[inline]
+    pub fn with_proxy(mut self, proxy: ProxyConfig) -> Result<Adapter<Srv>, Error> {
+        self.set_proxy(proxy)?;
+        Ok(self)
+    }
+
+    /// Convert this adapter into one using the given TLS options.
+    #[inline]
+    pub fn with_tls_config(mut self, tls: TlsConfig) -> Result<Adapter<Srv>, Error> {
+        self.set_tls_config(tls)?;
+        Ok(self)
+    }
+
     /// Convert this adapter into one using the given endpoint interface.
     #[inline]
     pub fn with_endpoint_interface<S>(mut self, endpoint_interface: S) -> Adapter<Srv>
@@ -159,9 +283,107 @@ impl<Srv> Adapter<Srv> {
         self.set_endpoint_interface(endpoint_interface);
         self
     }
+
+    /// Convert this adapter into one using the given endpoint filter.
+    #[inline]
+    pub fn with_endpoint_filter(mut self, filter: EndpointFilter) -> Adapter<Srv> {
+        self.set_endpoint_filter(filter);
+        self
+    }
+
+    /// Convert this adapter into one using the given region.
+    #[inline]
+    pub fn with_region<S>(mut self, region: S) -> Adapter<Srv>
+    where
+        S: Into<String>,
+    {
+        self.set_region(region);
+        self
+    }
+
+    /// Convert this adapter into one using the given rules to rewrite endpoint URLs.
+    #[inline]
+    pub fn with_rewrite_rules(mut self, rules: RewriteRules) -> Adapter<Srv> {
+        self.set_rewrite_rules(rules);
+        self
+    }
+
+    /// Convert this adapter into one using the given failover cool-down.
+    #[inline]
+    pub fn with_failover_cooldown(mut self, cooldown: Duration) -> Adapter<Srv> {
+        self.set_failover_cooldown(cooldown);
+        self
+    }
+
+    /// Convert this adapter into one using the given time-to-live of cached service information.
+    #[inline]
+    pub fn with_service_info_ttl(mut self, ttl: Option<Duration>) -> Adapter<Srv> {
+        self.set_service_info_ttl(ttl);
+        self
+    }
 }
 
 impl<Srv: ServiceType + Send + Clone> Adapter<Srv> {
+    /// Create a copy of this adapter using the given region.
+    ///
+    /// The copy shares the authentication and the cached information with this adapter, which
+    /// makes it cheap to use for a single request.
+    #[inline]
+    pub fn in_region<S>(&self, region: S) -> Adapter<Srv>
+    where
+        S: Into<String>,
+    {
+        self.clone().with_region(region)
+    }
+
+    /// Drop the cached information about the service.
+    ///
+    /// See [Session::invalidate_service_info](struct.Session.html#method.invalidate_service_info)
+    /// for details.
+    #[inline]
+    pub fn invalidate_service_info(&self) {
+        self.inner.invalidate_service_info(self.service.clone());
+    }
+
+    /// Run the same call in every region of the service.
+    ///
+    /// See [Session::for_each_region](struct.Session.html#method.for_each_region) for details.
+    ///
+    /// ```rust,no_run
+    /// use futures::Future;
+    ///
+    /// let adapter = osauth::Adapter::from_env(osauth::services::NETWORK)
+    ///     .expect("Failed to create an identity provider from the environment");
+    /// let future = adapter
+    ///     .for_each_region(|adapter| adapter.get_json::<_, serde_json::Value>(&["networks"], None))
+    ///     .map(|results| {
+    ///         for (region, result) in results {
+    ///             println!("{}: {:?}", region, result);
+    ///         }
+    ///     });
+    /// ```
+    pub fn for_each_region<F, R>(
+        &self,
+        func: F,
+    ) -> impl Future<Item = Vec<(String, Result<R::Item, Error>)>, Error = Error> + Send
+    where
+        F: Fn(Adapter<Srv>) -> R + Send,
+        R: IntoFuture<Error = Error>,
+        R::Future: Send,
+        R::Item: Send,
+    {
+        let service = self.service.clone();
+        let default_api_version = self.default_api_version;
+        self.inner
+            .for_each_region(self.service.clone(), move |session| {
+                func(Adapter {
+                    inner: session,
+                    service: service.clone(),
+                    default_api_version,
+                })
+            })
+    }
+
     /// Get minimum/maximum API (micro)version information.
     ///
     /// Returns `None` if the range cannot be determined, which usually means
@@ -332,8 +554,14 @@ impl<Srv: ServiceType + Send + Clone> Adapter<Srv> {
         I::Item: AsRef<str>,
         I::IntoIter: Send,
     {
-        self.start_get(path, api_version)
-            .then(request::send_checked)
+        let real_version = api_version.or(self.default_api_version);
+        self.inner.send_checked(
+            self.service.clone(),
+            Method::GET,
+            path,
+            real_version,
+            |builder| builder,
+        )
     }
 
     /// Fetch a JSON using the GET request.
@@ -378,7 +606,14 @@ impl<Srv: ServiceType + Send + Clone> Adapter<Srv> {
         I::IntoIter: Send,
         T: DeserializeOwned + Send,
     {
-        self.start_get(path, api_version).then(request::fetch_json)
+        let real_version = api_version.or(self.default_api_version);
+        self.inner.fetch_json(
+            self.service.clone(),
+            Method::GET,
+            path,
+            real_version,
+            |builder| builder,
+        )
     }
 
     /// Fetch a JSON using the GET request with a query.
@@ -399,9 +634,14 @@ impl<Srv: ServiceType + Send + Clone> Adapter<Srv> {
         Q: Serialize + Send,
         T: DeserializeOwned + Send,
     {
-        self.start_get(path, api_version)
-            .map(move |builder| builder.query(&query))
-            .then(request::fetch_json)
+        let real_version = api_version.or(self.default_api_version);
+        self.inner.fetch_json(
+            self.service.clone(),
+            Method::GET,
+            path,
+            real_version,
+            move |builder| builder.query(&query),
+        )
     }
 
     /// Issue a GET request with a query
@@ -421,9 +661,14 @@ impl<Srv: ServiceType + Send + Clone> Adapter<Srv> {
         I::IntoIter: Send,
         Q: Serialize + Send,
     {
-        self.start_get(path, api_version)
-            .map(move |builder| builder.query(&query))
-            .then(request::send_checked)
+        let real_version = api_version.or(self.default_api_version);
+        self.inner.send_checked(
+            self.service.clone(),
+            Method::GET,
+            path,
+            real_version,
+            move |builder| builder.query(&query),
+        )
     }
 
     /// Start a POST request.
@@ -461,9 +706,14 @@ impl<Srv: ServiceType + Send + Clone> Adapter<Srv> {
         I::IntoIter: Send,
         T: Serialize + Send,
     {
-        self.start_post(path, api_version)
-            .map(move |builder| builder.json(&body))
-            .then(request::send_checked)
+        let real_version = api_version.or(self.default_api_version);
+        self.inner.send_checked(
+            self.service.clone(),
+            Method::POST,
+            path,
+            real_version,
+            move |builder| builder.json(&body),
+        )
     }
 
     /// POST a JSON object and receive a JSON back.
@@ -485,9 +735,14 @@ impl<Srv: ServiceType + Send + Clone> Adapter<Srv> {
         T: Serialize + Send,
         R: DeserializeOwned + Send,
     {
-        self.start_post(path, api_version)
-            .map(move |builder| builder.json(&body))
-            .then(request::fetch_json)
+        let real_version = api_version.or(self.default_api_version);
+        self.inner.fetch_json(
+            self.service.clone(),
+            Method::POST,
+            path,
+            real_version,
+            move |builder| builder.json(&body),
+        )
     }
 
     /// Start a PUT request.
@@ -525,9 +780,14 @@ impl<Srv: ServiceType + Send + Clone> Adapter<Srv> {
         I::IntoIter: Send,
         T: Serialize + Send,
     {
-        self.start_put(path, api_version)
-            .map(move |builder| builder.json(&body))
-            .then(request::send_checked)
+        let real_version = api_version.or(self.default_api_version);
+        self.inner.send_checked(
+            self.service.clone(),
+            Method::PUT,
+            path,
+            real_version,
+            move |builder| builder.json(&body),
+        )
     }
 
     /// Issue an empty PUT request.
@@ -544,8 +804,14 @@ impl<Srv: ServiceType + Send + Clone> Adapter<Srv> {
         I::Item: AsRef<str>,
         I::IntoIter: Send,
     {
-        self.start_put(path, api_version)
-            .then(request::send_checked)
+        let real_version = api_version.or(self.default_api_version);
+        self.inner.send_checked(
+            self.service.clone(),
+            Method::PUT,
+            path,
+            real_version,
+            |builder| builder,
+        )
     }
 
     /// PUT a JSON object and receive a JSON back.
@@ -567,9 +833,14 @@ impl<Srv: ServiceType + Send + Clone> Adapter<Srv> {
         T: Serialize + Send,
         R: DeserializeOwned + Send,
     {
-        self.start_put(path, api_version)
-            .map(move |builder| builder.json(&body))
-            .then(request::fetch_json)
+        let real_version = api_version.or(self.default_api_version);
+        self.inner.fetch_json(
+            self.service.clone(),
+            Method::PUT,
+            path,
+            real_version,
+            move |builder| builder.json(&body),
+        )
     }
 
     /// Start a DELETE request.
@@ -603,7 +874,13 @@ impl<Srv: ServiceType + Send + Clone> Adapter<Srv> {
         I::Item: AsRef<str>,
         I::IntoIter: Send,
     {
-        self.start_delete(path, api_version)
-            .then(request::send_checked)
+        let real_version = api_version.or(self.default_api_version);
+        self.inner.send_checked(
+            self.service.clone(),
+            Method::DELETE,
+            path,
+            real_version,
+            |builder| builder,
+        )
     }
 }
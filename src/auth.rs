@@ -14,13 +14,17 @@
 
 //! Base code for authentication.
 
-use std::fmt::Debug;
+use std::collections::HashMap;
+use std::fmt::{self, Debug};
+use std::path::Path;
 
 use futures::{future, Future};
 use reqwest::r#async::{Client, RequestBuilder};
 use reqwest::{IntoUrl, Method, Url};
 
-use super::Error;
+use super::catalog::{self, Catalog};
+use super::secret::SecretString;
+use super::{EndpointFilter, Error, SecretSource};
 
 /// Trait for an authentication type.
 ///
@@ -36,22 +40,117 @@ pub trait AuthType: Debug + Sync + Send {
         &self,
         service_type: String,
         endpoint_interface: Option<String>,
-    ) -> Box<Future<Item = Url, Error = Error> + Send>;
+    ) -> Box<dyn Future<Item = Url, Error = Error> + Send>;
+
+    /// Get a URL for the requested service using an endpoint filter.
+    ///
+    /// Uses the region of the authentication if the filter has no region. The default
+    /// implementation only uses the first interface of the filter and is suitable for
+    /// authentication types without a service catalog.
+    fn find_endpoint(
+        &self,
+        service_type: String,
+        filter: EndpointFilter,
+    ) -> Box<dyn Future<Item = Url, Error = Error> + Send> {
+        self.get_endpoint(service_type, filter.interfaces().first().cloned())
+    }
+
+    /// Get all URLs for the requested service using an endpoint filter.
+    ///
+    /// Several URLs are returned if the service catalog lists several matching endpoints, e.g. in
+    /// high availability deployments. The default implementation returns the result of
+    /// [find_endpoint](#method.find_endpoint).
+    fn find_endpoints(
+        &self,
+        service_type: String,
+        filter: EndpointFilter,
+    ) -> Box<dyn Future<Item = Vec<Url>, Error = Error> + Send> {
+        Box::new(
+            self.find_endpoint(service_type, filter)
+                .map(|endpoint| vec![endpoint]),
+        )
+    }
+
+    /// Get the service catalog.
+    ///
+    /// The default implementation returns an empty catalog and is suitable for authentication
+    /// types without a service catalog.
+    fn get_catalog(&self) -> Box<dyn Future<Item = Catalog, Error = Error> + Send> {
+        Box::new(future::ok(Catalog::default()))
+    }
 
     /// Create an authenticated request.
     fn request(
         &self,
         method: Method,
         url: Url,
-    ) -> Box<Future<Item = RequestBuilder, Error = Error> + Send>;
+    ) -> Box<dyn Future<Item = RequestBuilder, Error = Error> + Send>;
 
     /// Refresh the authentication (renew the token, etc).
-    fn refresh(&self) -> Box<Future<Item = (), Error = Error> + Send>;
+    fn refresh(&self) -> Box<dyn Future<Item = (), Error = Error> + Send>;
 
     /// Region used with this authentication (if any).
     fn region(&self) -> Option<String> {
         None
     }
+
+    /// Create a copy of this authentication type that uses the given HTTP client.
+    ///
+    /// Returns `None` if the authentication type does not support changing its HTTP client,
+    /// which is the default.
+    fn clone_with_client(&self, _client: Client) -> Option<Box<dyn AuthType>> {
+        None
+    }
+}
+
+impl<T: AuthType + ?Sized> AuthType for Box<T> {
+    fn get_endpoint(
+        &self,
+        service_type: String,
+        endpoint_interface: Option<String>,
+    ) -> Box<dyn Future<Item = Url, Error = Error> + Send> {
+        (**self).get_endpoint(service_type, endpoint_interface)
+    }
+
+    fn find_endpoint(
+        &self,
+        service_type: String,
+        filter: EndpointFilter,
+    ) -> Box<dyn Future<Item = Url, Error = Error> + Send> {
+        (**self).find_endpoint(service_type, filter)
+    }
+
+    fn find_endpoints(
+        &self,
+        service_type: String,
+        filter: EndpointFilter,
+    ) -> Box<dyn Future<Item = Vec<Url>, Error = Error> + Send> {
+        (**self).find_endpoints(service_type, filter)
+    }
+
+    fn get_catalog(&self) -> Box<dyn Future<Item = Catalog, Error = Error> + Send> {
+        (**self).get_catalog()
+    }
+
+    fn request(
+        &self,
+        method: Method,
+        url: Url,
+    ) -> Box<dyn Future<Item = RequestBuilder, Error = Error> + Send> {
+        (**self).request(method, url)
+    }
+
+    fn refresh(&self) -> Box<dyn Future<Item = (), Error = Error> + Send> {
+        (**self).refresh()
+    }
+
+    fn region(&self) -> Option<String> {
+        (**self).region()
+    }
+
+    fn clone_with_client(&self, client: Client) -> Option<Box<dyn AuthType>> {
+        (**self).clone_with_client(client)
+    }
 }
 
 /// Authentication type that provides no authentication.
@@ -74,11 +173,19 @@ impl NoAuth {
     /// This endpoint will be returned in response to all `get_endpoint` calls
     /// of the [AuthType](trait.AuthType.html) trait.
     pub fn new<U>(endpoint: U) -> Result<NoAuth, Error>
+    where
+        U: IntoUrl,
+    {
+        NoAuth::new_with_client(endpoint, Client::new())
+    }
+
+    /// Create a new fake authentication method with the provided HTTP client.
+    pub fn new_with_client<U>(endpoint: U, client: Client) -> Result<NoAuth, Error>
     where
         U: IntoUrl,
     {
         Ok(NoAuth {
-            client: Client::new(),
+            client,
             endpoint: endpoint.into_url()?,
         })
     }
@@ -90,7 +197,7 @@ impl AuthType for NoAuth {
         &self,
         method: Method,
         url: Url,
-    ) -> Box<Future<Item = RequestBuilder, Error = Error> + Send> {
+    ) -> Box<dyn Future<Item = RequestBuilder, Error = Error> + Send> {
         Box::new(future::ok(self.client.request(method, url)))
     }
 
@@ -99,21 +206,605 @@ impl AuthType for NoAuth {
         &self,
         _service_type: String,
         _endpoint_interface: Option<String>,
-    ) -> Box<Future<Item = Url, Error = Error> + Send> {
+    ) -> Box<dyn Future<Item = Url, Error = Error> + Send> {
         Box::new(future::ok(self.endpoint.clone()))
     }
 
     /// This call does nothing for `NoAuth`.
-    fn refresh(&self) -> Box<Future<Item = (), Error = Error> + Send> {
+    fn refresh(&self) -> Box<dyn Future<Item = (), Error = Error> + Send> {
+        Box::new(future::ok(()))
+    }
+
+    fn clone_with_client(&self, client: Client) -> Option<Box<dyn AuthType>> {
+        Some(Box::new(NoAuth {
+            client,
+            endpoint: self.endpoint.clone(),
+        }))
+    }
+}
+
+/// Authentication type that uses HTTP basic authentication.
+///
+/// This type always uses a pre-defined endpoint:
+/// ```rust,no_run
+/// let auth = osauth::BasicAuth::new("https://cloud.local/baremetal", "admin", "pa$$w0rd")
+///     .expect("Invalid endpoint URL");
+/// let session = osauth::Session::new(auth);
+/// ```
+#[derive(Clone)]
+pub struct BasicAuth {
+    client: Client,
+    endpoint: Url,
+    username: String,
+    password: SecretString,
+}
+
+impl Debug for BasicAuth {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("BasicAuth")
+            .field("client", &self.client)
+            .field("endpoint", &self.endpoint)
+            .field("username", &self.username)
+            .field("password", &"***")
+            .finish()
+    }
+}
+
+impl BasicAuth {
+    /// Create a new HTTP basic authentication using a fixed endpoint.
+    ///
+    /// This endpoint will be returned in response to all `get_endpoint` calls
+    /// of the [AuthType](trait.AuthType.html) trait.
+    pub fn new<U, S1, S2>(endpoint: U, username: S1, password: S2) -> Result<BasicAuth, Error>
+    where
+        U: IntoUrl,
+        S1: Into<String>,
+        S2: Into<String>,
+    {
+        BasicAuth::new_with_client(endpoint, Client::new(), username, password)
+    }
+
+    /// Create a new HTTP basic authentication with the password from the given source.
+    pub fn new_with_secret<U, S>(
+        endpoint: U,
+        username: S,
+        password: &SecretSource,
+    ) -> Result<BasicAuth, Error>
+    where
+        U: IntoUrl,
+        S: Into<String>,
+    {
+        BasicAuth::new(endpoint, username, password.resolve()?)
+    }
+
+    /// Create a new HTTP basic authentication with the provided HTTP client.
+    pub fn new_with_client<U, S1, S2>(
+        endpoint: U,
+        client: Client,
+        username: S1,
+        password: S2,
+    ) -> Result<BasicAuth, Error>
+    where
+        U: IntoUrl,
+        S1: Into<String>,
+        S2: Into<String>,
+    {
+        Ok(BasicAuth {
+            client,
+            endpoint: endpoint.into_url()?,
+            username: username.into(),
+            password: password.into().into(),
+        })
+    }
+}
+
+impl AuthType for BasicAuth {
+    /// Create a request with HTTP basic authentication.
+    fn request(
+        &self,
+        method: Method,
+        url: Url,
+    ) -> Box<dyn Future<Item = RequestBuilder, Error = Error> + Send> {
+        Box::new(future::ok(
+            self.client
+                .request(method, url)
+                .basic_auth(&self.username, Some(self.password.as_str())),
+        ))
+    }
+
+    /// Get a predefined endpoint for all service types
+    fn get_endpoint(
+        &self,
+        _service_type: String,
+        _endpoint_interface: Option<String>,
+    ) -> Box<dyn Future<Item = Url, Error = Error> + Send> {
+        Box::new(future::ok(self.endpoint.clone()))
+    }
+
+    /// This call does nothing for `BasicAuth`.
+    fn refresh(&self) -> Box<dyn Future<Item = (), Error = Error> + Send> {
+        Box::new(future::ok(()))
+    }
+
+    fn clone_with_client(&self, client: Client) -> Option<Box<dyn AuthType>> {
+        let mut result = self.clone();
+        result.client = client;
+        Some(Box::new(result))
+    }
+}
+
+/// Authentication type that uses a static token with a fixed endpoint.
+///
+/// This corresponds to the `admin_token` authentication type of `clouds.yaml`. The token is sent
+/// as is, no requests are made to the Identity service:
+/// ```rust,no_run
+/// let auth = osauth::AdminToken::new("https://cloud.local/identity/v3", "<a token>")
+///     .expect("Invalid endpoint URL");
+/// let session = osauth::Session::new(auth);
+/// ```
+#[derive(Clone)]
+pub struct AdminToken {
+    client: Client,
+    endpoint: Url,
+    token: SecretString,
+}
+
+impl Debug for AdminToken {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("AdminToken")
+            .field("client", &self.client)
+            .field("endpoint", &self.endpoint)
+            .field("token", &"***")
+            .finish()
+    }
+}
+
+impl AdminToken {
+    /// Create a new static token authentication using a fixed endpoint.
+    ///
+    /// This endpoint will be returned in response to all `get_endpoint` calls
+    /// of the [AuthType](trait.AuthType.html) trait.
+    pub fn new<U, S>(endpoint: U, token: S) -> Result<AdminToken, Error>
+    where
+        U: IntoUrl,
+        S: Into<String>,
+    {
+        AdminToken::new_with_client(endpoint, Client::new(), token)
+    }
+
+    /// Create a new static token authentication with the token from the given source.
+    #[inline]
+    pub fn new_with_secret<U: IntoUrl>(
+        endpoint: U,
+        token: &SecretSource,
+    ) -> Result<AdminToken, Error> {
+        AdminToken::new(endpoint, token.resolve()?)
+    }
+
+    /// Create a new static token authentication with the provided HTTP client.
+    pub fn new_with_client<U, S>(endpoint: U, client: Client, token: S) -> Result<AdminToken, Error>
+    where
+        U: IntoUrl,
+        S: Into<String>,
+    {
+        Ok(AdminToken {
+            client,
+            endpoint: endpoint.into_url()?,
+            token: token.into().into(),
+        })
+    }
+}
+
+impl AuthType for AdminToken {
+    /// Create a request with the static token.
+    fn request(
+        &self,
+        method: Method,
+        url: Url,
+    ) -> Box<dyn Future<Item = RequestBuilder, Error = Error> + Send> {
+        Box::new(future::ok(
+            self.client
+                .request(method, url)
+                .header("x-auth-token", self.token.as_str()),
+        ))
+    }
+
+    /// Get a predefined endpoint for all service types
+    fn get_endpoint(
+        &self,
+        _service_type: String,
+        _endpoint_interface: Option<String>,
+    ) -> Box<dyn Future<Item = Url, Error = Error> + Send> {
+        Box::new(future::ok(self.endpoint.clone()))
+    }
+
+    /// This call does nothing for `AdminToken`.
+    fn refresh(&self) -> Box<dyn Future<Item = (), Error = Error> + Send> {
         Box::new(future::ok(()))
     }
+
+    fn clone_with_client(&self, client: Client) -> Option<Box<dyn AuthType>> {
+        let mut result = self.clone();
+        result.client = client;
+        Some(Box::new(result))
+    }
+}
+
+/// Authentication type that uses a static token with a static service catalog.
+///
+/// No requests are made to the Identity service, the endpoints are selected from the catalog the
+/// same way as for the [identity](identity/index.html) authentication types. This is useful for
+/// air-gapped testing environments:
+/// ```rust,no_run
+/// let catalog = osauth::catalog::Catalog::from_file("catalog.yaml")
+///     .expect("Cannot load the catalog");
+/// let auth = osauth::StaticCatalog::new("<a token>", catalog).with_region("RegionOne");
+/// let session = osauth::Session::new(auth);
+/// ```
+#[derive(Clone)]
+pub struct StaticCatalog {
+    client: Client,
+    token: SecretString,
+    catalog: Catalog,
+    region: Option<String>,
+    endpoint_interface: String,
+}
+
+impl Debug for StaticCatalog {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("StaticCatalog")
+            .field("client", &self.client)
+            .field("token", &"***")
+            .field("catalog", &self.catalog)
+            .field("region", &self.region)
+            .field("endpoint_interface", &self.endpoint_interface)
+            .finish()
+    }
+}
+
+impl StaticCatalog {
+    /// Create a new static catalog authentication.
+    ///
+    /// The `public` endpoint interface is used by default.
+    pub fn new<S: Into<String>>(token: S, catalog: Catalog) -> StaticCatalog {
+        StaticCatalog::new_with_client(Client::new(), token, catalog)
+    }
+
+    /// Create a new static catalog authentication with the token from the given source.
+    #[inline]
+    pub fn new_with_secret(token: &SecretSource, catalog: Catalog) -> Result<StaticCatalog, Error> {
+        Ok(StaticCatalog::new(token.resolve()?, catalog))
+    }
+
+    /// Create a new static catalog authentication with the provided HTTP client.
+    pub fn new_with_client<S: Into<String>>(
+        client: Client,
+        token: S,
+        catalog: Catalog,
+    ) -> StaticCatalog {
+        StaticCatalog {
+            client,
+            token: token.into().into(),
+            catalog,
+            region: None,
+            endpoint_interface: "public".to_string(),
+        }
+    }
+
+    /// Create a new static catalog authentication with the catalog from a JSON or YAML file.
+    ///
+    /// See [Catalog::from_yaml](catalog/struct.Catalog.html#method.from_yaml) for the supported
+    /// formats.
+    #[inline]
+    pub fn from_file<S, P>(token: S, path: P) -> Result<StaticCatalog, Error>
+    where
+        S: Into<String>,
+        P: AsRef<Path>,
+    {
+        Ok(StaticCatalog::new(token, Catalog::from_file(path)?))
+    }
+
+    /// The service catalog.
+    #[inline]
+    pub fn catalog(&self) -> &Catalog {
+        &self.catalog
+    }
+
+    /// The default endpoint interface.
+    #[inline]
+    pub fn default_endpoint_interface(&self) -> &String {
+        &self.endpoint_interface
+    }
+
+    /// Set the default endpoint interface to use.
+    #[inline]
+    pub fn set_default_endpoint_interface<S>(&mut self, endpoint_interface: S)
+    where
+        S: Into<String>,
+    {
+        self.endpoint_interface = endpoint_interface.into();
+    }
+
+    /// Set the HTTP client to use for requests.
+    #[inline]
+    pub fn set_client(&mut self, client: Client) {
+        self.client = client;
+    }
+
+    /// Set a region for this authentication method.
+    #[inline]
+    pub fn set_region<S>(&mut self, region: S)
+    where
+        S: Into<String>,
+    {
+        self.region = Some(region.into());
+    }
+
+    /// Convert this authentication into one using the given endpoint interface.
+    #[inline]
+    pub fn with_default_endpoint_interface<S>(mut self, endpoint_interface: S) -> Self
+    where
+        S: Into<String>,
+    {
+        self.set_default_endpoint_interface(endpoint_interface);
+        self
+    }
+
+    /// Convert this authentication into one using the given HTTP client.
+    #[inline]
+    pub fn with_client(mut self, client: Client) -> Self {
+        self.set_client(client);
+        self
+    }
+
+    /// Convert this authentication into one using the given region.
+    #[inline]
+    pub fn with_region<S>(mut self, region: S) -> Self
+    where
+        S: Into<String>,
+    {
+        self.set_region(region);
+        self
+    }
+}
+
+impl AuthType for StaticCatalog {
+    /// Create a request with the static token.
+    fn request(
+        &self,
+        method: Method,
+        url: Url,
+    ) -> Box<dyn Future<Item = RequestBuilder, Error = Error> + Send> {
+        Box::new(future::ok(
+            self.client
+                .request(method, url)
+                .header("x-auth-token", self.token.as_str()),
+        ))
+    }
+
+    /// Get a URL for the requested service from the static catalog.
+    fn get_endpoint(
+        &self,
+        service_type: String,
+        endpoint_interface: Option<String>,
+    ) -> Box<dyn Future<Item = Url, Error = Error> + Send> {
+        let mut filter = EndpointFilter::new();
+        if let Some(endpoint_interface) = endpoint_interface {
+            filter.set_interfaces(vec![endpoint_interface]);
+        }
+        self.find_endpoint(service_type, filter)
+    }
+
+    /// Find a URL for the requested service in the static catalog.
+    ///
+    /// The region and the default endpoint interface are used if the filter does not specify
+    /// them.
+    fn find_endpoint(
+        &self,
+        service_type: String,
+        mut filter: EndpointFilter,
+    ) -> Box<dyn Future<Item = Url, Error = Error> + Send> {
+        filter.fill_defaults(&self.endpoint_interface, self.region.as_deref());
+        Box::new(future::result(
+            filter.find_url(self.catalog.records(), &service_type),
+        ))
+    }
+
+    /// Find all URLs for the requested service in the static catalog.
+    fn find_endpoints(
+        &self,
+        service_type: String,
+        mut filter: EndpointFilter,
+    ) -> Box<dyn Future<Item = Vec<Url>, Error = Error> + Send> {
+        filter.fill_defaults(&self.endpoint_interface, self.region.as_deref());
+        Box::new(future::result(
+            filter.find_urls(self.catalog.records(), &service_type),
+        ))
+    }
+
+    /// Get the static service catalog.
+    fn get_catalog(&self) -> Box<dyn Future<Item = Catalog, Error = Error> + Send> {
+        Box::new(future::ok(self.catalog.clone()))
+    }
+
+    /// This call does nothing for `StaticCatalog`.
+    fn refresh(&self) -> Box<dyn Future<Item = (), Error = Error> + Send> {
+        Box::new(future::ok(()))
+    }
+
+    /// Region used with this authentication (if any).
+    fn region(&self) -> Option<String> {
+        self.region.clone()
+    }
+
+    fn clone_with_client(&self, client: Client) -> Option<Box<dyn AuthType>> {
+        Some(Box::new(self.clone().with_client(client)))
+    }
+}
+
+/// Authentication wrapper that uses fixed endpoints for some service types.
+///
+/// Authentication is delegated to the wrapped type, while the listed service types bypass the
+/// service catalog, for example, to reach a service through an SSH tunnel:
+/// ```rust,no_run
+/// let auth = osauth::identity::Password::new(
+///     "https://cloud.local/identity",
+///     "admin",
+///     "pa$$w0rd",
+///     "Default"
+/// )
+/// .expect("Invalid auth URL");
+/// let auth = osauth::EndpointOverride::new(auth)
+///     .with_endpoint("baremetal", "http://localhost:6385")
+///     .expect("Invalid endpoint URL");
+/// let session = osauth::Session::new(auth);
+/// ```
+#[derive(Clone, Debug)]
+pub struct EndpointOverride<A> {
+    inner: A,
+    endpoints: HashMap<String, Url>,
+}
+
+impl<A: AuthType> EndpointOverride<A> {
+    /// Wrap an authentication type, initially without overrides.
+    #[inline]
+    pub fn new(inner: A) -> EndpointOverride<A> {
+        EndpointOverride {
+            inner,
+            endpoints: HashMap::new(),
+        }
+    }
+
+    /// The wrapped authentication type.
+    #[inline]
+    pub fn inner(&self) -> &A {
+        &self.inner
+    }
+
+    /// Convert into the wrapped authentication type.
+    #[inline]
+    pub fn into_inner(self) -> A {
+        self.inner
+    }
+
+    /// Endpoint override for the service type (if any).
+    ///
+    /// Aliases of service types are resolved, e.g. `volumev3` returns the override for
+    /// `block-storage`.
+    pub fn endpoint(&self, service_type: &str) -> Option<&Url> {
+        let service_types = catalog::service_types();
+        let official = service_types
+            .official_type(service_type)
+            .unwrap_or(service_type);
+        self.endpoints.get(official)
+    }
+
+    /// Use a fixed endpoint for the service type.
+    pub fn set_endpoint<S, U>(&mut self, service_type: S, endpoint: U) -> Result<(), Error>
+    where
+        S: Into<String>,
+        U: IntoUrl,
+    {
+        let service_type = service_type.into();
+        let official = catalog::service_types()
+            .official_type(&service_type)
+            .map(ToString::to_string)
+            .unwrap_or(service_type);
+        let _ = self.endpoints.insert(official, endpoint.into_url()?);
+        Ok(())
+    }
+
+    /// Use a fixed endpoint for the service type.
+    #[inline]
+    pub fn with_endpoint<S, U>(mut self, service_type: S, endpoint: U) -> Result<Self, Error>
+    where
+        S: Into<String>,
+        U: IntoUrl,
+    {
+        self.set_endpoint(service_type, endpoint)?;
+        Ok(self)
+    }
+}
+
+impl<A: AuthType> AuthType for EndpointOverride<A> {
+    /// Create an authenticated request using the wrapped authentication.
+    fn request(
+        &self,
+        method: Method,
+        url: Url,
+    ) -> Box<dyn Future<Item = RequestBuilder, Error = Error> + Send> {
+        self.inner.request(method, url)
+    }
+
+    /// Get the endpoint override or the endpoint from the wrapped authentication.
+    fn get_endpoint(
+        &self,
+        service_type: String,
+        endpoint_interface: Option<String>,
+    ) -> Box<dyn Future<Item = Url, Error = Error> + Send> {
+        match self.endpoint(&service_type) {
+            Some(endpoint) => Box::new(future::ok(endpoint.clone())),
+            None => self.inner.get_endpoint(service_type, endpoint_interface),
+        }
+    }
+
+    /// Get the endpoint override or the endpoint from the wrapped authentication.
+    fn find_endpoint(
+        &self,
+        service_type: String,
+        filter: EndpointFilter,
+    ) -> Box<dyn Future<Item = Url, Error = Error> + Send> {
+        match self.endpoint(&service_type) {
+            Some(endpoint) => Box::new(future::ok(endpoint.clone())),
+            None => self.inner.find_endpoint(service_type, filter),
+        }
+    }
+
+    /// Get the endpoint override or the endpoints from the wrapped authentication.
+    fn find_endpoints(
+        &self,
+        service_type: String,
+        filter: EndpointFilter,
+    ) -> Box<dyn Future<Item = Vec<Url>, Error = Error> + Send> {
+        match self.endpoint(&service_type) {
+            Some(endpoint) => Box::new(future::ok(vec![endpoint.clone()])),
+            None => self.inner.find_endpoints(service_type, filter),
+        }
+    }
+
+    /// Get the service catalog of the wrapped authentication.
+    fn get_catalog(&self) -> Box<dyn Future<Item = Catalog, Error = Error> + Send> {
+        self.inner.get_catalog()
+    }
+
+    /// Refresh the wrapped authentication.
+    fn refresh(&self) -> Box<dyn Future<Item = (), Error = Error> + Send> {
+        self.inner.refresh()
+    }
+
+    /// Region of the wrapped authentication.
+    fn region(&self) -> Option<String> {
+        self.inner.region()
+    }
+
+    /// Copy the wrapped authentication with the given HTTP client.
+    fn clone_with_client(&self, client: Client) -> Option<Box<dyn AuthType>> {
+        let inner = self.inner.clone_with_client(client)?;
+        Some(Box::new(EndpointOverride {
+            inner,
+            endpoints: self.endpoints.clone(),
+        }))
+    }
 }
 
 #[cfg(test)]
 pub mod test {
     use futures::Future;
+    use reqwest::{Method, Url};
 
-    use super::{AuthType, NoAuth};
+    use super::super::catalog::Catalog;
+    use super::super::{EndpointFilter, ErrorKind};
+    use super::{AdminToken, AuthType, BasicAuth, EndpointOverride, NoAuth, StaticCatalog};
 
     #[test]
     fn test_noauth_new() {
@@ -139,4 +830,131 @@ pub mod test {
         assert_eq!(e.port().unwrap(), 8080u16);
         assert_eq!(e.path(), "/v1");
     }
+
+    #[test]
+    fn test_noauth_boxed() {
+        let a: Box<dyn AuthType> = Box::new(NoAuth::new("http://127.0.0.1:8080/v1").unwrap());
+        let e = a.get_endpoint(String::from("foobar"), None).wait().unwrap();
+        assert_eq!(e.as_str(), "http://127.0.0.1:8080/v1");
+    }
+
+    #[test]
+    fn test_basic_auth_get_endpoint() {
+        let a = BasicAuth::new("http://127.0.0.1:8080/v1", "admin", "pa$$w0rd").unwrap();
+        let e = a.get_endpoint(String::from("foobar"), None).wait().unwrap();
+        assert_eq!(e.as_str(), "http://127.0.0.1:8080/v1");
+    }
+
+    #[test]
+    fn test_admin_token_debug() {
+        let a = AdminToken::new("http://127.0.0.1:8080/v1", "abcdef").unwrap();
+        assert!(!format!("{:?}", a).contains("abcdef"));
+        let e = a.get_endpoint(String::from("foobar"), None).wait().unwrap();
+        assert_eq!(e.as_str(), "http://127.0.0.1:8080/v1");
+    }
+
+    #[test]
+    fn test_endpoint_override() {
+        let a = EndpointOverride::new(NoAuth::new("http://127.0.0.1:8080/v1").unwrap())
+            .with_endpoint("baremetal", "http://localhost:6385")
+            .unwrap()
+            .with_endpoint("volumev3", "http://localhost:8776/v3")
+            .unwrap();
+        assert!(a.endpoint("compute").is_none());
+
+        let e = a
+            .get_endpoint(String::from("baremetal"), None)
+            .wait()
+            .unwrap();
+        assert_eq!(e.as_str(), "http://localhost:6385/");
+        let e = a
+            .get_endpoint(String::from("block-storage"), None)
+            .wait()
+            .unwrap();
+        assert_eq!(e.as_str(), "http://localhost:8776/v3");
+        let e = a
+            .get_endpoint(String::from("compute"), None)
+            .wait()
+            .unwrap();
+        assert_eq!(e.as_str(), "http://127.0.0.1:8080/v1");
+
+        let _ = EndpointOverride::new(a.into_inner())
+            .with_endpoint("baremetal", "foo bar")
+            .err()
+            .unwrap();
+    }
+
+    const CATALOG: &str = r#"
+- type: baremetal
+  name: ironic
+  endpoints:
+    - interface: public
+      region: RegionOne
+      url: https://host.one/baremetal
+    - interface: internal
+      region: RegionOne
+      url: http://192.168.22.1/baremetal
+    - interface: public
+      region: RegionTwo
+      url: https://host.two:6385
+- type: volumev3
+  endpoints:
+    - interface: public
+      region: RegionOne
+      url: https://host.one/volume/v3
+"#;
+
+    #[test]
+    fn test_static_catalog_get_endpoint() {
+        let catalog = Catalog::from_yaml(CATALOG).unwrap();
+        let a = StaticCatalog::new("token", catalog).with_region("RegionOne");
+        assert_eq!(a.region(), Some(String::from("RegionOne")));
+
+        let e = a
+            .get_endpoint(String::from("baremetal"), None)
+            .wait()
+            .unwrap();
+        assert_eq!(e.as_str(), "https://host.one/baremetal");
+        let e = a
+            .get_endpoint(String::from("baremetal"), Some(String::from("internal")))
+            .wait()
+            .unwrap();
+        assert_eq!(e.as_str(), "http://192.168.22.1/baremetal");
+        let e = a
+            .get_endpoint(String::from("block-storage"), None)
+            .wait()
+            .unwrap();
+        assert_eq!(e.as_str(), "https://host.one/volume/v3");
+        let e = a
+            .find_endpoint(
+                String::from("baremetal"),
+                EndpointFilter::new().with_region("RegionTwo"),
+            )
+            .wait()
+            .unwrap();
+        assert_eq!(e.as_str(), "https://host.two:6385/");
+
+        let err = a
+            .get_endpoint(String::from("compute"), None)
+            .wait()
+            .err()
+            .unwrap();
+        assert_eq!(err.kind(), ErrorKind::EndpointNotFound);
+
+        let cat = a.get_catalog().wait().unwrap();
+        assert_eq!(cat.service_types(), vec!["baremetal", "volumev3"]);
+    }
+
+    #[test]
+    fn test_static_catalog_request() {
+        let a = StaticCatalog::new("token", Catalog::default());
+        let req = a
+            .request(Method::GET, Url::parse("http://localhost/").unwrap())
+            .wait()
+            .unwrap()
+            .build()
+            .unwrap();
+        assert_eq!(req.headers().get("x-auth-token").unwrap(), "token");
+        assert!(!format!("{:?}", a).contains("token\""));
+    }
 }
@@ -84,17 +84,34 @@ impl<K: Hash + Eq, V> MapCache<K, V> {
         guard.get(key).map(filter)
     }
 
-    /// Whether a value is set.
-    #[inline]
-    pub fn is_set(&self, key: &K) -> bool {
-        let guard = self.0.read().expect("Cache lock is poisoned");
-        guard.contains_key(key)
-    }
-
     /// Set a new value.
     #[inline]
     pub fn set(&self, key: K, value: V) {
         let mut guard = self.0.write().expect("Cache lock is poisoned");
         let _ = guard.insert(key, value);
     }
+
+    /// Remove a value if it matches the predicate.
+    #[inline]
+    pub fn remove_if<F>(&self, key: &K, predicate: F) -> Option<V>
+    where
+        F: FnOnce(&V) -> bool,
+    {
+        let mut guard = self.0.write().expect("Cache lock is poisoned");
+        if guard.get(key).map(predicate).unwrap_or(false) {
+            guard.remove(key)
+        } else {
+            None
+        }
+    }
+
+    /// Retain only the values matching the predicate.
+    #[inline]
+    pub fn retain<F>(&self, predicate: F)
+    where
+        F: FnMut(&K, &mut V) -> bool,
+    {
+        let mut guard = self.0.write().expect("Cache lock is poisoned");
+        guard.retain(predicate);
+    }
 }
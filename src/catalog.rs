@@ -12,117 +12,790 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-//! Low-level code to work with the service catalog.
-
-use osproto::identity::{CatalogRecord, Endpoint};
-
-use super::Error;
-
-/// Find an endpoint in the service catalog.
-pub fn find_endpoint<'c>(
-    catalog: &'c [CatalogRecord],
-    service_type: &str,
-    endpoint_interface: &str,
-    region: &Option<String>,
-) -> Result<&'c Endpoint, Error> {
-    let svc = match catalog.iter().find(|x| x.service_type == *service_type) {
-        Some(s) => s,
-        None => return Err(Error::new_endpoint_not_found(service_type)),
-    };
+//! Service catalog and endpoint selection.
+//!
+//! Endpoints are selected using an [EndpointFilter](struct.EndpointFilter.html). If the
+//! endpoints matching a service span several regions and no region is set, the selection fails
+//! with `TooManyItems` instead of silently using the first region.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::sync::{Arc, OnceLock, RwLock};
+
+use log::{debug, error, warn};
+use reqwest::Url;
+use serde::Deserialize;
+
+use super::{Error, ErrorKind};
+
+const BUILTIN_SERVICE_TYPES: &str = include_str!("service-types.json");
+
+/// An endpoint in the service catalog.
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq)]
+pub struct Endpoint {
+    /// Endpoint ID.
+    #[serde(default)]
+    pub id: Option<String>,
+    /// Endpoint interface, e.g. `public` or `internal`.
+    pub interface: String,
+    /// Region name.
+    #[serde(default)]
+    pub region: Option<String>,
+    /// Region ID.
+    #[serde(default)]
+    pub region_id: Option<String>,
+    /// Endpoint URL.
+    pub url: String,
+}
+
+/// A service catalog record.
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq)]
+pub struct CatalogRecord {
+    /// Service ID.
+    #[serde(default)]
+    pub id: Option<String>,
+    /// Service name, e.g. `nova`.
+    #[serde(default)]
+    pub name: Option<String>,
+    /// Service type, e.g. `compute`.
+    #[serde(rename = "type")]
+    pub service_type: String,
+    /// Endpoints of the service.
+    #[serde(default)]
+    pub endpoints: Vec<Endpoint>,
+}
+
+/// Placeholders for the project ID in catalog URLs.
+const PROJECT_TEMPLATES: [&str; 4] = [
+    "$(project_id)s",
+    "$(tenant_id)s",
+    "%(project_id)s",
+    "%(tenant_id)s",
+];
 
-    let maybe_endp: Option<&Endpoint>;
-    if let Some(ref rgn) = *region {
-        maybe_endp = svc
-            .endpoints
+impl Endpoint {
+    /// Whether the URL contains project ID placeholders, e.g. `$(project_id)s`.
+    pub fn is_templated(&self) -> bool {
+        PROJECT_TEMPLATES
             .iter()
-            .find(|x| x.interface == *endpoint_interface && x.region == *rgn);
-    } else {
-        maybe_endp = svc
-            .endpoints
+            .any(|template| self.url.contains(template))
+    }
+
+    /// Replace project ID placeholders in the URL, e.g. `$(project_id)s`.
+    pub fn resolve_templates(&mut self, project_id: &str) {
+        for template in PROJECT_TEMPLATES.iter() {
+            if self.url.contains(template) {
+                self.url = self.url.replace(template, project_id);
+            }
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct CatalogInToken {
+    catalog: Vec<CatalogRecord>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum CatalogDocument {
+    Records(Vec<CatalogRecord>),
+    Catalog { catalog: Vec<CatalogRecord> },
+    Token { token: CatalogInToken },
+}
+
+/// A service catalog.
+///
+/// ```rust,no_run
+/// use futures::Future;
+///
+/// let session =
+///     osauth::from_env().expect("Failed to create an identity provider from the environment");
+/// let catalog = session.catalog().wait().expect("Cannot fetch the catalog");
+/// for service_type in catalog.service_types() {
+///     println!("{} is available in {:?}", service_type, catalog.regions_of(service_type));
+/// }
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Catalog {
+    records: Vec<CatalogRecord>,
+}
+
+impl Catalog {
+    /// Create a catalog from its records.
+    #[inline]
+    pub fn new(records: Vec<CatalogRecord>) -> Catalog {
+        Catalog { records }
+    }
+
+    /// Parse a catalog from a JSON or YAML document.
+    ///
+    /// The document is either a list of catalog records, a mapping with a `catalog` key or
+    /// a token in the format of the Identity API (a mapping with a `token` key).
+    pub fn from_yaml(data: &str) -> Result<Catalog, Error> {
+        let doc: CatalogDocument = serde_yaml::from_str(data).map_err(|e| {
+            Error::new(
+                ErrorKind::InvalidInput,
+                format!("Cannot parse service catalog: {}", e),
+            )
+        })?;
+        Ok(Catalog::new(match doc {
+            CatalogDocument::Records(records) => records,
+            CatalogDocument::Catalog { catalog } => catalog,
+            CatalogDocument::Token { token } => token.catalog,
+        }))
+    }
+
+    /// Load a catalog from a JSON or YAML file.
+    ///
+    /// See [from_yaml](#method.from_yaml) for the supported formats.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Catalog, Error> {
+        let path = path.as_ref();
+        let data = fs::read_to_string(path).map_err(|e| {
+            Error::new(
+                ErrorKind::InvalidInput,
+                format!("Cannot read service catalog from {}: {}", path.display(), e),
+            )
+        })?;
+        Catalog::from_yaml(&data)
+    }
+
+    /// Records of the catalog, one per service.
+    #[inline]
+    pub fn records(&self) -> &[CatalogRecord] {
+        &self.records
+    }
+
+    /// Convert into the records of the catalog.
+    #[inline]
+    pub fn into_records(self) -> Vec<CatalogRecord> {
+        self.records
+    }
+
+    /// Whether the catalog has no services.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.records.is_empty()
+    }
+
+    /// Records of the given service type, including its aliases.
+    pub fn services<'c>(&'c self, service_type: &str) -> Vec<&'c CatalogRecord> {
+        let service_types = service_types();
+        let all_types = service_types.all_types(service_type);
+        self.records
             .iter()
-            .find(|x| x.interface == *endpoint_interface);
+            .filter(|svc| all_types.contains(&svc.service_type.as_str()))
+            .collect()
+    }
+
+    /// Sorted service types in the catalog, as they appear in it.
+    pub fn service_types(&self) -> Vec<&str> {
+        sorted(self.records.iter().map(|svc| svc.service_type.as_str()))
+    }
+
+    /// Sorted names of regions of all endpoints.
+    pub fn regions(&self) -> Vec<&str> {
+        sorted(self.endpoints().filter_map(|endp| endp.region.as_deref()))
+    }
+
+    /// Sorted names of regions of the given service type, including its aliases.
+    pub fn regions_of(&self, service_type: &str) -> Vec<&str> {
+        sorted(
+            self.services(service_type)
+                .into_iter()
+                .flat_map(|svc| svc.endpoints.iter())
+                .filter_map(|endp| endp.region.as_deref()),
+        )
+    }
+
+    /// Sorted interfaces of all endpoints.
+    pub fn interfaces(&self) -> Vec<&str> {
+        sorted(self.endpoints().map(|endp| endp.interface.as_str()))
+    }
+
+    /// Replace project ID placeholders in the URLs of all endpoints.
+    ///
+    /// Catalogs returned by the Identity service already have them replaced for project-scoped
+    /// tokens.
+    pub fn resolve_templates(&mut self, project_id: &str) {
+        resolve_templates(&mut self.records, project_id);
+    }
+
+    /// Find an endpoint using the filter.
+    #[inline]
+    pub fn find_endpoint(
+        &self,
+        service_type: &str,
+        filter: &EndpointFilter,
+    ) -> Result<&Endpoint, Error> {
+        filter.find(&self.records, service_type)
+    }
+
+    /// Find all endpoints using the filter, e.g. for failover between them.
+    #[inline]
+    pub fn find_endpoints(
+        &self,
+        service_type: &str,
+        filter: &EndpointFilter,
+    ) -> Result<Vec<&Endpoint>, Error> {
+        filter.find_all(&self.records, service_type)
+    }
+
+    fn endpoints(&self) -> impl Iterator<Item = &Endpoint> {
+        self.records.iter().flat_map(|svc| svc.endpoints.iter())
+    }
+}
+
+impl From<Vec<CatalogRecord>> for Catalog {
+    fn from(records: Vec<CatalogRecord>) -> Catalog {
+        Catalog::new(records)
+    }
+}
+
+pub(crate) fn resolve_templates(records: &mut [CatalogRecord], project_id: &str) {
+    for endp in records.iter_mut().flat_map(|svc| svc.endpoints.iter_mut()) {
+        endp.resolve_templates(project_id);
+    }
+}
+
+fn sorted<'c, I: Iterator<Item = &'c str>>(items: I) -> Vec<&'c str> {
+    let mut result: Vec<_> = items.collect();
+    result.sort_unstable();
+    result.dedup();
+    result
+}
+
+#[derive(Debug, Deserialize)]
+struct ServiceTypeRecord {
+    service_type: String,
+    #[serde(default)]
+    aliases: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ServiceTypesRoot {
+    services: Vec<ServiceTypeRecord>,
+}
+
+/// Official service types and their historical aliases.
+///
+/// The data follows the format of `service-types.json` from the OpenStack service types
+/// authority. A copy of it is built in, use [set_service_types](fn.set_service_types.html) to
+/// load an updated one.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ServiceTypes {
+    aliases: HashMap<String, Vec<String>>,
+    official: HashMap<String, String>,
+}
+
+impl ServiceTypes {
+    /// Service types built into this crate.
+    pub fn builtin() -> ServiceTypes {
+        ServiceTypes::from_json(BUILTIN_SERVICE_TYPES).expect("Invalid built-in service types")
+    }
+
+    /// Parse the contents of `service-types.json`.
+    pub fn from_json(data: &str) -> Result<ServiceTypes, Error> {
+        let root: ServiceTypesRoot = serde_json::from_str(data).map_err(|e| {
+            Error::new(
+                ErrorKind::InvalidInput,
+                format!("Cannot parse service types: {}", e),
+            )
+        })?;
+
+        let mut result = ServiceTypes::default();
+        for record in root.services {
+            for alias in &record.aliases {
+                let _ = result
+                    .official
+                    .insert(alias.clone(), record.service_type.clone());
+            }
+            let _ = result.aliases.insert(record.service_type, record.aliases);
+        }
+        Ok(result)
+    }
+
+    /// Load `service-types.json` from the given file.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<ServiceTypes, Error> {
+        let path = path.as_ref();
+        let data = fs::read_to_string(path).map_err(|e| {
+            Error::new(
+                ErrorKind::InvalidInput,
+                format!("Cannot read service types from {}: {}", path.display(), e),
+            )
+        })?;
+        ServiceTypes::from_json(&data)
+    }
+
+    /// Official service type for the given type or alias.
+    ///
+    /// Returns `None` for unknown service types.
+    pub fn official_type<'s>(&'s self, service_type: &'s str) -> Option<&'s str> {
+        if self.aliases.contains_key(service_type) {
+            Some(service_type)
+        } else {
+            self.official.get(service_type).map(String::as_str)
+        }
+    }
+
+    /// Historical aliases of the given official service type.
+    pub fn aliases(&self, service_type: &str) -> &[String] {
+        self.aliases
+            .get(service_type)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    /// All types to try in the service catalog for the given type, in the order of preference.
+    ///
+    /// The requested type comes first, followed by the official type and its aliases.
+    pub fn all_types<'s>(&'s self, service_type: &'s str) -> Vec<&'s str> {
+        let mut result = vec![service_type];
+        if let Some(official) = self.official_type(service_type) {
+            for candidate in
+                std::iter::once(official).chain(self.aliases(official).iter().map(String::as_str))
+            {
+                if !result.contains(&candidate) {
+                    result.push(candidate);
+                }
+            }
+        }
+        result
+    }
+}
+
+fn service_types_lock() -> &'static RwLock<Arc<ServiceTypes>> {
+    static SERVICE_TYPES: OnceLock<RwLock<Arc<ServiceTypes>>> = OnceLock::new();
+    SERVICE_TYPES.get_or_init(|| RwLock::new(Arc::new(ServiceTypes::builtin())))
+}
+
+/// Service types used for catalog lookups.
+pub fn service_types() -> Arc<ServiceTypes> {
+    Arc::clone(
+        &service_types_lock()
+            .read()
+            .expect("Service types lock is poisoned"),
+    )
+}
+
+/// Replace the service types used for catalog lookups, e.g. with an updated
+/// `service-types.json`.
+///
+/// ```rust,no_run
+/// let types = osauth::catalog::ServiceTypes::from_file("/etc/openstack/service-types.json")
+///     .expect("Cannot load service types");
+/// osauth::catalog::set_service_types(types);
+/// ```
+pub fn set_service_types(service_types: ServiceTypes) {
+    *service_types_lock()
+        .write()
+        .expect("Service types lock is poisoned") = Arc::new(service_types);
+}
+
+/// Criteria to select an endpoint from the service catalog.
+///
+/// Interfaces are tried in the order of preference, the first one with a matching endpoint wins:
+///
+/// ```rust,no_run
+/// let filter = osauth::EndpointFilter::new()
+///     .with_interfaces(vec!["internal", "public"])
+///     .with_region("RegionOne");
+/// let session = osauth::from_env()
+///     .expect("Failed to create an identity provider from the environment")
+///     .with_endpoint_filter(filter);
+/// ```
+///
+/// Endpoints from several regions are an error (of kind `TooManyItems`) unless a region is set,
+/// so set one for multi-region catalogs. Several endpoints in the same region (e.g. in HA
+/// deployments) are not an error, [find](#method.find) returns the first one in the order of
+/// the catalog. Endpoints from several regions are never used for failover.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct EndpointFilter {
+    interfaces: Vec<String>,
+    region: Option<String>,
+    service_name: Option<String>,
+    endpoint_id: Option<String>,
+}
+
+impl EndpointFilter {
+    /// Create a filter matching any endpoint.
+    #[inline]
+    pub fn new() -> EndpointFilter {
+        EndpointFilter::default()
+    }
+
+    /// Endpoint interfaces in the order of preference, any interface if empty.
+    #[inline]
+    pub fn interfaces(&self) -> &[String] {
+        &self.interfaces
+    }
+
+    /// Region name or ID (if any).
+    #[inline]
+    pub fn region(&self) -> Option<&str> {
+        self.region.as_deref()
+    }
+
+    /// Service name (if any).
+    #[inline]
+    pub fn service_name(&self) -> Option<&str> {
+        self.service_name.as_deref()
+    }
+
+    /// Endpoint ID (if any).
+    #[inline]
+    pub fn endpoint_id(&self) -> Option<&str> {
+        self.endpoint_id.as_deref()
+    }
+
+    /// Set endpoint interfaces in the order of preference.
+    pub fn set_interfaces<I, S>(&mut self, interfaces: I)
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.interfaces = interfaces.into_iter().map(Into::into).collect();
+    }
+
+    /// Set region name or ID.
+    #[inline]
+    pub fn set_region<S: Into<String>>(&mut self, region: S) {
+        self.region = Some(region.into());
+    }
+
+    /// Set service name.
+    #[inline]
+    pub fn set_service_name<S: Into<String>>(&mut self, service_name: S) {
+        self.service_name = Some(service_name.into());
+    }
+
+    /// Set endpoint ID.
+    #[inline]
+    pub fn set_endpoint_id<S: Into<String>>(&mut self, endpoint_id: S) {
+        self.endpoint_id = Some(endpoint_id.into());
+    }
+
+    /// Set endpoint interfaces in the order of preference.
+    #[inline]
+    pub fn with_interfaces<I, S>(mut self, interfaces: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.set_interfaces(interfaces);
+        self
+    }
+
+    /// Set region name or ID.
+    #[inline]
+    pub fn with_region<S: Into<String>>(mut self, region: S) -> Self {
+        self.set_region(region);
+        self
+    }
+
+    /// Set service name.
+    #[inline]
+    pub fn with_service_name<S: Into<String>>(mut self, service_name: S) -> Self {
+        self.set_service_name(service_name);
+        self
+    }
+
+    /// Set endpoint ID.
+    #[inline]
+    pub fn with_endpoint_id<S: Into<String>>(mut self, endpoint_id: S) -> Self {
+        self.set_endpoint_id(endpoint_id);
+        self
+    }
+
+    /// Whether the endpoint matches the region and ID of this filter.
+    fn matches(&self, endpoint: &Endpoint) -> bool {
+        if let Some(ref region) = self.region {
+            if endpoint.region.as_ref() != Some(region)
+                && endpoint.region_id.as_ref() != Some(region)
+            {
+                return false;
+            }
+        }
+        match self.endpoint_id {
+            Some(ref id) => endpoint.id.as_ref() == Some(id),
+            None => true,
+        }
+    }
+
+    /// Find an endpoint for the service type in the service catalog.
+    ///
+    /// The official type and the aliases of the service type are tried in order, see
+    /// [ServiceTypes::all_types](struct.ServiceTypes.html#method.all_types). If several
+    /// endpoints match, the first one in the order of the catalog is used.
+    pub fn find<'c>(
+        &self,
+        catalog: &'c [CatalogRecord],
+        service_type: &str,
+    ) -> Result<&'c Endpoint, Error> {
+        let service_types = service_types();
+        for candidate in service_types.all_types(service_type) {
+            if let Some(endp) = self.find_exact(catalog, candidate)? {
+                if candidate != service_type {
+                    debug!(
+                        "Using service type {} for requested service {}",
+                        candidate, service_type
+                    );
+                }
+                return Ok(endp);
+            }
+        }
+        Err(Error::new_endpoint_not_found(service_type))
+    }
+
+    /// Find an endpoint URL for the service type in the service catalog.
+    pub(crate) fn find_url(
+        &self,
+        catalog: &[CatalogRecord],
+        service_type: &str,
+    ) -> Result<Url, Error> {
+        let endp = self.find(catalog, service_type)?;
+        debug!("Received {:?} for {}", endp, service_type);
+        self.parse_url(endp, service_type)
+    }
+
+    /// Find all endpoints for the service type in the service catalog.
+    ///
+    /// Endpoints with different URLs are returned in the order of the catalog, e.g. for failover
+    /// between them. Like in [find](#method.find), endpoints from several regions are an error
+    /// (of kind `TooManyItems`) if the filter does not specify a region.
+    pub fn find_all<'c>(
+        &self,
+        catalog: &'c [CatalogRecord],
+        service_type: &str,
+    ) -> Result<Vec<&'c Endpoint>, Error> {
+        let service_types = service_types();
+        for candidate in service_types.all_types(service_type) {
+            let mut found = self.candidates(catalog, candidate);
+            if !found.is_empty() {
+                let mut seen = Vec::with_capacity(found.len());
+                found.retain(|endp| {
+                    if seen.contains(&endp.url.as_str()) {
+                        false
+                    } else {
+                        seen.push(endp.url.as_str());
+                        true
+                    }
+                });
+                if self.region.is_none() {
+                    self.check_single_region(&found, candidate)?;
+                }
+                return Ok(found);
+            }
+        }
+        Err(Error::new_endpoint_not_found(service_type))
+    }
+
+    /// Find all endpoint URLs for the service type in the service catalog.
+    pub(crate) fn find_urls(
+        &self,
+        catalog: &[CatalogRecord],
+        service_type: &str,
+    ) -> Result<Vec<Url>, Error> {
+        let found = self.find_all(catalog, service_type)?;
+        debug!("Received {:?} for {}", found, service_type);
+        found
+            .into_iter()
+            .map(|endp| self.parse_url(endp, service_type))
+            .collect()
     }
 
-    maybe_endp.ok_or_else(|| Error::new_endpoint_not_found(service_type))
+    fn parse_url(&self, endp: &Endpoint, service_type: &str) -> Result<Url, Error> {
+        Url::parse(&endp.url).map_err(|e| {
+            error!(
+                "Invalid URL {} received from service catalog for service \
+                 '{}' with {:?}: {}",
+                endp.url, service_type, self, e
+            );
+            Error::new(
+                ErrorKind::InvalidResponse,
+                format!("Invalid URL {} for {} - {}", endp.url, service_type, e),
+            )
+        })
+    }
+
+    /// Use the interface and the region if the filter does not specify them.
+    pub(crate) fn fill_defaults(&mut self, interface: &str, region: Option<&str>) {
+        if self.interfaces.is_empty() && self.endpoint_id.is_none() {
+            self.interfaces = vec![interface.to_string()];
+        }
+        if self.region.is_none() {
+            self.region = region.map(ToString::to_string);
+        }
+    }
+
+    fn find_exact<'c>(
+        &self,
+        catalog: &'c [CatalogRecord],
+        service_type: &str,
+    ) -> Result<Option<&'c Endpoint>, Error> {
+        let candidates = self.candidates(catalog, service_type);
+        if self.region.is_none() {
+            self.check_single_region(&candidates, service_type)?;
+        }
+        match candidates.first() {
+            Some(first) if candidates.iter().any(|endp| endp.url != first.url) => {
+                debug!(
+                    "Several endpoints match service {} with {:?}, using the first one {}",
+                    service_type, self, first.url
+                );
+                Ok(Some(first))
+            }
+            first => Ok(first.cloned()),
+        }
+    }
+
+    fn check_single_region(&self, found: &[&Endpoint], service_type: &str) -> Result<(), Error> {
+        let mut regions = Vec::new();
+        for endp in found {
+            let region = endp.region.as_ref().or(endp.region_id.as_ref());
+            if !regions.contains(&region) {
+                regions.push(region);
+            }
+        }
+
+        if regions.len() > 1 {
+            warn!(
+                "Endpoints for service {} with {:?} are in several regions: {:?}",
+                service_type, self, regions
+            );
+            Err(Error::new(
+                ErrorKind::TooManyItems,
+                format!(
+                    "Endpoints for service {} found in several regions, set a region",
+                    service_type
+                ),
+            ))
+        } else {
+            Ok(())
+        }
+    }
+
+    fn candidates<'c>(
+        &self,
+        catalog: &'c [CatalogRecord],
+        service_type: &str,
+    ) -> Vec<&'c Endpoint> {
+        let endpoints = catalog
+            .iter()
+            .filter(|svc| svc.service_type == service_type)
+            .filter(|svc| match self.service_name {
+                Some(ref name) => svc.name.as_ref() == Some(name),
+                None => true,
+            })
+            .flat_map(|svc| svc.endpoints.iter())
+            .filter(|endp| self.matches(endp))
+            .collect::<Vec<_>>();
+
+        if self.interfaces.is_empty() {
+            endpoints
+        } else {
+            self.interfaces
+                .iter()
+                .map(|interface| {
+                    endpoints
+                        .iter()
+                        .filter(|endp| endp.interface == *interface)
+                        .cloned()
+                        .collect::<Vec<_>>()
+                })
+                .find(|found| !found.is_empty())
+                .unwrap_or_default()
+        }
+    }
 }
 
 #[cfg(test)]
-pub mod test {
-    use osproto::identity::{CatalogRecord, Endpoint};
+mod test {
+    use std::fs;
 
     use super::super::{Error, ErrorKind};
+    use super::{
+        set_service_types, Catalog, CatalogRecord, Endpoint, EndpointFilter, ServiceTypes,
+    };
+
+    fn endpoint(id: &str, interface: &str, region: &str, url: &str) -> Endpoint {
+        Endpoint {
+            id: Some(String::from(id)),
+            interface: String::from(interface),
+            region: Some(String::from(region)),
+            region_id: Some(region.to_lowercase()),
+            url: String::from(url),
+        }
+    }
 
     fn demo_service1() -> CatalogRecord {
         CatalogRecord {
+            id: Some(String::from("s1")),
+            name: Some(String::from("keystone")),
             service_type: String::from("identity"),
             endpoints: vec![
-                Endpoint {
-                    interface: String::from("public"),
-                    region: String::from("RegionOne"),
-                    url: String::from("https://host.one/identity"),
-                },
-                Endpoint {
-                    interface: String::from("internal"),
-                    region: String::from("RegionOne"),
-                    url: String::from("http://192.168.22.1/identity"),
-                },
-                Endpoint {
-                    interface: String::from("public"),
-                    region: String::from("RegionTwo"),
-                    url: String::from("https://host.two:5000"),
-                },
+                endpoint("e1", "public", "RegionOne", "https://host.one/identity"),
+                endpoint(
+                    "e2",
+                    "internal",
+                    "RegionOne",
+                    "http://192.168.22.1/identity",
+                ),
+                endpoint("e3", "public", "RegionTwo", "https://host.two:5000"),
             ],
         }
     }
 
     fn demo_service2() -> CatalogRecord {
         CatalogRecord {
+            id: Some(String::from("s2")),
+            name: Some(String::from("ironic")),
             service_type: String::from("baremetal"),
             endpoints: vec![
-                Endpoint {
-                    interface: String::from("public"),
-                    region: String::from("RegionOne"),
-                    url: String::from("https://host.one/baremetal"),
-                },
-                Endpoint {
-                    interface: String::from("public"),
-                    region: String::from("RegionTwo"),
-                    url: String::from("https://host.two:6385"),
-                },
+                endpoint("e4", "public", "RegionOne", "https://host.one/baremetal"),
+                endpoint("e5", "public", "RegionTwo", "https://host.two:6385"),
             ],
         }
     }
 
-    pub fn demo_catalog() -> Vec<CatalogRecord> {
+    fn demo_service3() -> CatalogRecord {
+        CatalogRecord {
+            id: Some(String::from("s3")),
+            name: Some(String::from("ironic-legacy")),
+            service_type: String::from("baremetal"),
+            endpoints: vec![endpoint(
+                "e6",
+                "internal",
+                "RegionOne",
+                "http://192.168.22.1/baremetal",
+            )],
+        }
+    }
+
+    fn demo_catalog() -> Vec<CatalogRecord> {
         vec![demo_service1(), demo_service2()]
     }
 
     fn find_endpoint<'a>(
-        cat: &'a Vec<CatalogRecord>,
+        cat: &'a [CatalogRecord],
         service_type: &str,
         interface_type: &str,
         region: Option<&str>,
     ) -> Result<&'a Endpoint, Error> {
-        super::find_endpoint(
-            cat,
-            &String::from(service_type),
-            &String::from(interface_type),
-            &region.map(String::from),
-        )
+        let mut filter = EndpointFilter::new().with_interfaces(vec![interface_type]);
+        if let Some(region) = region {
+            filter.set_region(region);
+        }
+        filter.find(cat, service_type)
     }
 
     #[test]
     fn test_find_endpoint() {
         let cat = demo_catalog();
 
-        let e1 = find_endpoint(&cat, "identity", "public", None).unwrap();
-        assert_eq!(&e1.url, "https://host.one/identity");
-
         let e2 = find_endpoint(&cat, "identity", "internal", None).unwrap();
         assert_eq!(&e2.url, "http://192.168.22.1/identity");
-
-        let e3 = find_endpoint(&cat, "baremetal", "public", None).unwrap();
-        assert_eq!(&e3.url, "https://host.one/baremetal");
     }
 
     #[test]
@@ -137,6 +810,122 @@ pub mod test {
 
         let e3 = find_endpoint(&cat, "baremetal", "public", Some("RegionTwo")).unwrap();
         assert_eq!(&e3.url, "https://host.two:6385");
+
+        let e4 = find_endpoint(&cat, "baremetal", "public", Some("regionone")).unwrap();
+        assert_eq!(&e4.url, "https://host.one/baremetal");
+    }
+
+    #[test]
+    fn test_find_endpoint_ambiguous() {
+        let cat = demo_catalog();
+
+        for service_type in &["identity", "baremetal"] {
+            let err = find_endpoint(&cat, service_type, "public", None)
+                .err()
+                .unwrap();
+            assert_eq!(err.kind(), ErrorKind::TooManyItems);
+        }
+
+        let err = EndpointFilter::new().find(&cat, "identity").err().unwrap();
+        assert_eq!(err.kind(), ErrorKind::TooManyItems);
+    }
+
+    #[test]
+    fn test_find_endpoint_same_region() {
+        let mut cat = demo_catalog();
+        cat[1].endpoints = vec![
+            endpoint("e4", "public", "RegionOne", "https://primary/baremetal"),
+            endpoint("e5", "public", "RegionOne", "https://secondary/baremetal"),
+        ];
+
+        let endp = find_endpoint(&cat, "baremetal", "public", None).unwrap();
+        assert_eq!(&endp.url, "https://primary/baremetal");
+        let endp = find_endpoint(&cat, "baremetal", "public", Some("RegionOne")).unwrap();
+        assert_eq!(&endp.url, "https://primary/baremetal");
+
+        let endp = EndpointFilter::new()
+            .with_region("RegionOne")
+            .find(&cat, "identity")
+            .unwrap();
+        assert_eq!(&endp.url, "https://host.one/identity");
+    }
+
+    #[test]
+    fn test_find_all_endpoints() {
+        let mut cat = demo_catalog();
+        cat.push(demo_service3());
+        let filter = EndpointFilter::new().with_region("RegionOne");
+        let found = filter.find_all(&cat, "baremetal").unwrap();
+        let urls = found.iter().map(|e| e.url.as_str()).collect::<Vec<_>>();
+        assert_eq!(
+            urls,
+            vec![
+                "https://host.one/baremetal",
+                "http://192.168.22.1/baremetal"
+            ]
+        );
+
+        let filter = filter.with_interfaces(vec!["public"]);
+        let found = filter.find_all(&cat, "baremetal").unwrap();
+        assert_eq!(found.len(), 1);
+
+        let err = filter.find_all(&cat, "compute").err().unwrap();
+        assert_eq!(err.kind(), ErrorKind::EndpointNotFound);
+
+        let filter = EndpointFilter::new().with_interfaces(vec!["public"]);
+        let err = filter.find_all(&cat, "baremetal").err().unwrap();
+        assert_eq!(err.kind(), ErrorKind::TooManyItems);
+    }
+
+    #[test]
+    fn test_find_endpoint_interfaces() {
+        let cat = demo_catalog();
+
+        let filter = EndpointFilter::new()
+            .with_interfaces(vec!["internal", "public"])
+            .with_region("RegionOne");
+        let e1 = filter.find(&cat, "identity").unwrap();
+        assert_eq!(&e1.url, "http://192.168.22.1/identity");
+        let e2 = filter.find(&cat, "baremetal").unwrap();
+        assert_eq!(&e2.url, "https://host.one/baremetal");
+
+        let filter = EndpointFilter::new()
+            .with_interfaces(vec!["admin", "internal"])
+            .with_region("RegionTwo");
+        assert_not_found(filter.find(&cat, "identity"));
+    }
+
+    #[test]
+    fn test_find_endpoint_by_name_and_id() {
+        let mut cat = demo_catalog();
+        cat.push(demo_service3());
+
+        let filter = EndpointFilter::new().with_interfaces(vec!["internal", "public"]);
+        let e1 = filter
+            .clone()
+            .with_service_name("ironic")
+            .with_region("RegionOne")
+            .find(&cat, "baremetal")
+            .unwrap();
+        assert_eq!(&e1.url, "https://host.one/baremetal");
+        let e2 = filter
+            .clone()
+            .with_region("RegionOne")
+            .find(&cat, "baremetal")
+            .unwrap();
+        assert_eq!(&e2.url, "http://192.168.22.1/baremetal");
+
+        let e3 = EndpointFilter::new()
+            .with_endpoint_id("e5")
+            .find(&cat, "baremetal")
+            .unwrap();
+        assert_eq!(&e3.url, "https://host.two:6385");
+        assert_not_found(
+            EndpointFilter::new()
+                .with_endpoint_id("e5")
+                .find(&cat, "identity"),
+        );
+        assert_not_found(filter.with_service_name("nova").find(&cat, "baremetal"));
     }
 
     fn assert_not_found(result: Result<&Endpoint, Error>) {
@@ -160,4 +949,215 @@ pub mod test {
             Some("RegionTwo"),
         ));
     }
+
+    #[test]
+    fn test_catalog_from_json() {
+        let cat: Vec<CatalogRecord> = serde_json::from_str(
+            r#"[{"type": "compute", "name": "nova", "id": "s1", "endpoints": [
+                {"id": "e1", "interface": "public", "region": "RegionOne",
+                 "region_id": "RegionOne", "url": "https://host.one/compute"},
+                {"id": "e2", "interface": "public", "region": null,
+                 "url": "https://host.any/compute"}
+            ]}]"#,
+        )
+        .unwrap();
+        assert_eq!(cat[0].name.as_ref().unwrap(), "nova");
+        assert_eq!(cat[0].endpoints[1].region, None);
+        let e1 = EndpointFilter::new()
+            .with_endpoint_id("e2")
+            .find(&cat, "compute")
+            .unwrap();
+        assert_eq!(&e1.url, "https://host.any/compute");
+    }
+
+    #[test]
+    fn test_catalog_from_yaml() {
+        let records = r#"[{"type": "compute", "endpoints": [
+            {"interface": "public", "url": "https://host.one/compute"}]}]"#;
+        let cat1 = Catalog::from_yaml(records).unwrap();
+        assert_eq!(cat1.service_types(), vec!["compute"]);
+
+        let cat2 = Catalog::from_yaml(&format!("{{\"catalog\": {}}}", records)).unwrap();
+        assert_eq!(cat2, cat1);
+
+        let token = format!(
+            "{{\"token\": {{\"expires_at\": \"2030-01-01T00:00:00Z\", \"catalog\": {}}}}}",
+            records
+        );
+        let cat3 = Catalog::from_yaml(&token).unwrap();
+        assert_eq!(cat3, cat1);
+
+        let cat4 = Catalog::from_yaml(
+            "catalog:\n  - type: compute\n    endpoints:\n      - interface: public\n        \
+             url: https://host.one/compute\n",
+        )
+        .unwrap();
+        assert_eq!(cat4, cat1);
+
+        let err = Catalog::from_yaml("services: []").err().unwrap();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+    }
+
+    fn volume_service(service_type: &str, url: &str) -> CatalogRecord {
+        CatalogRecord {
+            id: None,
+            name: Some(String::from("cinder")),
+            service_type: String::from(service_type),
+            endpoints: vec![endpoint("v1", "public", "RegionOne", url)],
+        }
+    }
+
+    #[test]
+    fn test_service_types_builtin() {
+        let types = ServiceTypes::builtin();
+        assert_eq!(types.official_type("volumev3"), Some("block-storage"));
+        assert_eq!(types.official_type("block-storage"), Some("block-storage"));
+        assert_eq!(types.official_type("sharev2"), Some("shared-file-system"));
+        assert_eq!(types.official_type("octavia"), Some("load-balancer"));
+        assert_eq!(types.official_type("foobar"), None);
+        assert!(types.aliases("compute").is_empty());
+        assert_eq!(
+            types.all_types("block-storage"),
+            vec![
+                "block-storage",
+                "volumev3",
+                "volumev2",
+                "volume",
+                "block-store"
+            ]
+        );
+        assert_eq!(
+            types.all_types("volumev2"),
+            vec![
+                "volumev2",
+                "block-storage",
+                "volumev3",
+                "volume",
+                "block-store"
+            ]
+        );
+        assert_eq!(types.all_types("foobar"), vec!["foobar"]);
+    }
+
+    #[test]
+    fn test_find_endpoint_aliases() {
+        let mut cat = demo_catalog();
+        cat.push(volume_service("volumev2", "https://host.one/volume/v2"));
+        cat.push(volume_service("volumev3", "https://host.one/volume/v3"));
+        let filter = EndpointFilter::new().with_interfaces(vec!["public"]);
+
+        let e1 = filter.find(&cat, "block-storage").unwrap();
+        assert_eq!(&e1.url, "https://host.one/volume/v3");
+        let e2 = filter.find(&cat, "volumev2").unwrap();
+        assert_eq!(&e2.url, "https://host.one/volume/v2");
+
+        cat.push(volume_service("block-storage", "https://host.one/volume"));
+        let e3 = filter.find(&cat, "block-storage").unwrap();
+        assert_eq!(&e3.url, "https://host.one/volume");
+        let e4 = filter.find(&cat, "volume").unwrap();
+        assert_eq!(&e4.url, "https://host.one/volume");
+    }
+
+    #[test]
+    fn test_service_types_custom() {
+        // Keep the built-in types since tests run in parallel.
+        let mut data: serde_json::Value =
+            serde_json::from_str(super::BUILTIN_SERVICE_TYPES).unwrap();
+        data["services"]
+            .as_array_mut()
+            .unwrap()
+            .push(serde_json::json!({
+                "service_type": "osauth-test",
+                "aliases": ["osauth-test-legacy"]
+            }));
+        let data = data.to_string();
+        let path = std::env::temp_dir().join(format!("osauth-types-{}.json", std::process::id()));
+        fs::write(&path, &data).unwrap();
+        let types = ServiceTypes::from_file(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(types, ServiceTypes::from_json(&data).unwrap());
+        assert_eq!(
+            types.official_type("osauth-test-legacy"),
+            Some("osauth-test")
+        );
+
+        let cat = vec![CatalogRecord {
+            id: None,
+            name: None,
+            service_type: String::from("osauth-test-legacy"),
+            endpoints: vec![endpoint(
+                "t1",
+                "public",
+                "RegionOne",
+                "https://host.one/test",
+            )],
+        }];
+        assert_not_found(EndpointFilter::new().find(&cat, "osauth-test"));
+        set_service_types(types);
+        let e1 = EndpointFilter::new().find(&cat, "osauth-test");
+        set_service_types(ServiceTypes::builtin());
+        assert_eq!(&e1.unwrap().url, "https://host.one/test");
+
+        let err = ServiceTypes::from_json("{}").err().unwrap();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn test_catalog() {
+        let mut records = demo_catalog();
+        records.push(volume_service("volumev3", "https://host.three/volume/v3"));
+        records[2].endpoints[0].region = Some(String::from("RegionThree"));
+        let cat = Catalog::from(records);
+
+        assert!(!cat.is_empty());
+        assert!(Catalog::default().is_empty());
+        assert_eq!(cat.records().len(), 3);
+        assert_eq!(
+            cat.service_types(),
+            vec!["baremetal", "identity", "volumev3"]
+        );
+        assert_eq!(cat.regions(), vec!["RegionOne", "RegionThree", "RegionTwo"]);
+        assert_eq!(cat.regions_of("baremetal"), vec!["RegionOne", "RegionTwo"]);
+        assert_eq!(cat.regions_of("block-storage"), vec!["RegionThree"]);
+        assert!(cat.regions_of("compute").is_empty());
+        assert_eq!(cat.interfaces(), vec!["internal", "public"]);
+
+        let services = cat.services("block-storage");
+        assert_eq!(services.len(), 1);
+        assert_eq!(services[0].name.as_ref().unwrap(), "cinder");
+
+        let filter = EndpointFilter::new().with_region("RegionThree");
+        let e1 = cat.find_endpoint("block-storage", &filter).unwrap();
+        assert_eq!(&e1.url, "https://host.three/volume/v3");
+    }
+
+    #[test]
+    fn test_resolve_templates() {
+        let mut cat = Catalog::new(vec![
+            volume_service("block-storage", "https://host.one/volume/v3/$(project_id)s"),
+            volume_service(
+                "object-store",
+                "https://host.one/swift/v1/AUTH_%(tenant_id)s",
+            ),
+            volume_service("compute", "https://host.one/compute/v2.1"),
+        ]);
+        assert!(cat.records()[0].endpoints[0].is_templated());
+        assert!(cat.records()[1].endpoints[0].is_templated());
+        assert!(!cat.records()[2].endpoints[0].is_templated());
+
+        cat.resolve_templates("abcd");
+        let urls = cat
+            .records()
+            .iter()
+            .map(|svc| svc.endpoints[0].url.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            urls,
+            vec![
+                "https://host.one/volume/v3/abcd",
+                "https://host.one/swift/v1/AUTH_abcd",
+                "https://host.one/compute/v2.1",
+            ]
+        );
+    }
 }
@@ -0,0 +1,775 @@
+// Copyright 2019 Dmitry Tantsur <divius.inside@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Validation of cloud configuration.
+
+use std::collections::HashSet;
+use std::fmt;
+use std::fs::File;
+
+use reqwest::Url;
+use serde_yaml::{Mapping, Value};
+
+use super::super::{ApiVersion, ProxyConfig};
+use super::{
+    auth_plugins, resolve_profile, AuthConfig, CloudConfig, ConfigFiles, Region, SECRETS,
+    SECRET_SOURCES, SERVICE_KEYS,
+};
+
+/// Severity of a validation issue.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Severity {
+    /// The configuration cannot be used.
+    Error,
+    /// The configuration can be used, but is likely not what was intended.
+    Warning,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        })
+    }
+}
+
+/// A problem found in a cloud configuration.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ValidationIssue {
+    severity: Severity,
+    path: String,
+    message: String,
+}
+
+impl ValidationIssue {
+    /// Severity of the issue.
+    #[inline]
+    pub fn severity(&self) -> Severity {
+        self.severity
+    }
+
+    /// Path to the key with the issue, e.g. `clouds.devstack.auth.auth_url`.
+    #[inline]
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// Description of the issue.
+    #[inline]
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+impl fmt::Display for ValidationIssue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}: {}", self.severity, self.path, self.message)
+    }
+}
+
+/// Result of validating a cloud configuration.
+///
+/// ```rust,no_run
+/// let report = osauth::config::validate("devstack").expect("Cannot load the cloud");
+/// for issue in report.issues() {
+///     println!("{}", issue);
+/// }
+/// assert!(report.is_valid());
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ValidationReport {
+    issues: Vec<ValidationIssue>,
+}
+
+impl ValidationReport {
+    /// All issues found.
+    #[inline]
+    pub fn issues(&self) -> &[ValidationIssue] {
+        &self.issues
+    }
+
+    /// Issues with the given severity.
+    pub fn issues_with(&self, severity: Severity) -> impl Iterator<Item = &ValidationIssue> {
+        self.issues
+            .iter()
+            .filter(move |issue| issue.severity == severity)
+    }
+
+    /// Whether no errors were found (warnings are allowed).
+    #[inline]
+    pub fn is_valid(&self) -> bool {
+        self.issues_with(Severity::Error).next().is_none()
+    }
+
+    fn error<S1: Into<String>, S2: Into<String>>(&mut self, path: S1, message: S2) {
+        self.add(Severity::Error, path.into(), message.into());
+    }
+
+    fn warning<S1: Into<String>, S2: Into<String>>(&mut self, path: S1, message: S2) {
+        self.add(Severity::Warning, path.into(), message.into());
+    }
+
+    fn add(&mut self, severity: Severity, path: String, message: String) {
+        self.issues.push(ValidationIssue {
+            severity,
+            path,
+            message,
+        });
+    }
+
+    fn with_prefix(mut self, prefix: &str) -> ValidationReport {
+        for issue in &mut self.issues {
+            issue.path = format!("{}.{}", prefix, issue.path);
+        }
+        self
+    }
+}
+
+impl fmt::Display for ValidationReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for issue in &self.issues {
+            writeln!(f, "{}", issue)?;
+        }
+        Ok(())
+    }
+}
+
+fn is_service_key(key: &str) -> bool {
+    SERVICE_KEYS.iter().any(|suffix| {
+        key.strip_suffix(suffix)
+            .and_then(|prefix| prefix.strip_suffix('_'))
+            .is_some_and(|prefix| !prefix.is_empty())
+    })
+}
+
+fn is_secret_source_key(key: &str) -> bool {
+    SECRETS.iter().any(|secret| {
+        SECRET_SOURCES
+            .iter()
+            .any(|source| key == format!("{}_{}", secret, source))
+    })
+}
+
+fn check_url(report: &mut ValidationReport, path: &str, value: &Option<String>) {
+    if let Some(value) = value {
+        match Url::parse(value) {
+            Ok(url) if url.scheme() == "http" || url.scheme() == "https" => {}
+            Ok(url) => report.error(path, format!("unsupported URL scheme {}", url.scheme())),
+            Err(e) => report.error(path, format!("invalid URL {}: {}", value, e)),
+        }
+    }
+}
+
+fn check_file(report: &mut ValidationReport, path: &str, value: &Option<String>) {
+    if let Some(value) = value {
+        if let Err(e) = File::open(value) {
+            report.error(path, format!("cannot read {}: {}", value, e));
+        }
+    }
+}
+
+fn check_required(report: &mut ValidationReport, auth_type: &str, names: &[&str], present: bool) {
+    if !present {
+        let message = if names.len() > 1 {
+            format!(
+                "one of {} is required for auth_type {}",
+                names.join(", "),
+                auth_type
+            )
+        } else {
+            format!("required for auth_type {}", auth_type)
+        };
+        report.error(format!("auth.{}", names[0]), message);
+    }
+}
+
+impl AuthConfig {
+    fn has_secret(&self, name: &str) -> bool {
+        self.secret_source(name).ok().and_then(|x| x).is_some()
+    }
+
+    fn has_user(&self) -> bool {
+        self.user_id.is_some() || self.username.is_some()
+    }
+}
+
+impl CloudConfig {
+    /// Check this configuration without connecting to the cloud.
+    ///
+    /// Paths in the report are relative to the cloud, e.g. `auth.auth_url`. Use
+    /// [validate](fn.validate.html) to also find keys that cannot be parsed.
+    pub fn validate(&self) -> ValidationReport {
+        let mut report = ValidationReport::default();
+        self.validate_keys(&mut report);
+        self.validate_auth(&mut report);
+
+        let auth = &self.auth;
+        check_url(&mut report, "auth.auth_url", &auth.auth_url);
+        check_url(&mut report, "auth.endpoint", &auth.endpoint);
+        if let Some(ref proxy) = self.http_proxy {
+            if let Err(e) = ProxyConfig::new().set_http_proxy(proxy.as_str()) {
+                report.error("http_proxy", e.to_string());
+            }
+        }
+        if let Some(ref proxy) = self.https_proxy {
+            if let Err(e) = ProxyConfig::new().set_https_proxy(proxy.as_str()) {
+                report.error("https_proxy", e.to_string());
+            }
+        }
+
+        check_file(&mut report, "cacert", &self.cacert);
+        check_file(&mut report, "cert", &self.cert);
+        check_file(&mut report, "key", &self.key);
+        if self.key.is_some() && self.cert.is_none() {
+            report.error("key", "a client key requires cert");
+        }
+        #[cfg(not(feature = "rustls"))]
+        {
+            if self.cert.is_some() {
+                report.error(
+                    "cert",
+                    "client certificates in the PEM format require the rustls feature",
+                );
+            }
+        }
+        if self.verify == Some(false) {
+            report.warning("verify", "TLS certificates are not verified");
+        }
+
+        if let Some(version) = self.identity_api_version() {
+            if version != "3" && !version.starts_with("3.") {
+                report.error(
+                    "identity_api_version",
+                    format!("identity API version {} is not supported", version),
+                );
+            }
+        }
+
+        for service_type in self.service_types() {
+            let prefix = service_type.replace('-', "_");
+            let override_key = format!("{}_endpoint_override", prefix);
+            check_url(
+                &mut report,
+                &override_key,
+                &self.endpoint_override(&service_type),
+            );
+            if let Some(version) = self.api_version(&service_type) {
+                if version.parse::<ApiVersion>().is_err() {
+                    report.error(
+                        format!("{}_api_version", prefix),
+                        format!("invalid API version {}", version),
+                    );
+                }
+            }
+        }
+
+        self.validate_regions(&mut report);
+        report
+    }
+
+    fn validate_keys(&self, report: &mut ValidationReport) {
+        let mut keys: Vec<&String> = self
+            .extra
+            .keys()
+            .filter(|key| !is_service_key(key))
+            .collect();
+        keys.sort();
+        for key in keys {
+            report.warning(key.as_str(), "unknown key");
+        }
+
+        // Custom plugins may use any keys.
+        if self.is_builtin_auth_type() {
+            let mut keys: Vec<&String> = self
+                .auth
+                .extra
+                .keys()
+                .filter(|key| !is_secret_source_key(key))
+                .collect();
+            keys.sort();
+            for key in keys {
+                report.warning(format!("auth.{}", key), "unknown key");
+            }
+        }
+    }
+
+    fn is_builtin_auth_type(&self) -> bool {
+        matches!(
+            self.auth_type(),
+            "password"
+                | "v3password"
+                | "token"
+                | "v3token"
+                | "v3applicationcredential"
+                | "v3multifactor"
+                | "none"
+                | "http_basic"
+                | "admin_token"
+        )
+    }
+
+    fn validate_auth(&self, report: &mut ValidationReport) {
+        let auth = &self.auth;
+        let auth_type = self.auth_type();
+        if !auth_plugins()
+            .read()
+            .expect("Auth plugins lock is poisoned")
+            .contains_key(auth_type)
+        {
+            report.error("auth_type", format!("unsupported auth_type {}", auth_type));
+            return;
+        }
+
+        for name in SECRETS.iter() {
+            if let Err(e) = auth.secret_source(name) {
+                report.error(format!("auth.{}", name), e.to_string());
+            }
+        }
+
+        let user = auth.has_user();
+        match auth_type {
+            "password" | "v3password" => {
+                check_required(report, auth_type, &["auth_url"], auth.auth_url.is_some());
+                check_required(report, auth_type, &["username", "user_id"], user);
+                check_required(
+                    report,
+                    auth_type,
+                    &["password"],
+                    auth.has_secret("password"),
+                );
+            }
+            "token" | "v3token" => {
+                check_required(report, auth_type, &["auth_url"], auth.auth_url.is_some());
+                check_required(report, auth_type, &["token"], auth.has_secret("token"));
+            }
+            "v3applicationcredential" => {
+                check_required(report, auth_type, &["auth_url"], auth.auth_url.is_some());
+                check_required(
+                    report,
+                    auth_type,
+                    &["application_credential_secret"],
+                    auth.has_secret("application_credential_secret"),
+                );
+                check_required(
+                    report,
+                    auth_type,
+                    &["application_credential_id", "application_credential_name"],
+                    auth.application_credential_id.is_some()
+                        || auth.application_credential_name.is_some(),
+                );
+                if auth.application_credential_id.is_none()
+                    && auth.application_credential_name.is_some()
+                {
+                    check_required(report, auth_type, &["username", "user_id"], user);
+                }
+                if auth.project_id.is_some()
+                    || auth.project_name.is_some()
+                    || auth.domain_id.is_some()
+                    || auth.domain_name.is_some()
+                    || auth.system_scope.is_some()
+                {
+                    report.warning(
+                        "auth",
+                        "application credentials cannot be scoped, the scope is ignored",
+                    );
+                }
+            }
+            "v3multifactor" => {
+                check_required(report, auth_type, &["auth_url"], auth.auth_url.is_some());
+                let methods = auth.auth_methods.as_deref().unwrap_or_default();
+                check_required(report, auth_type, &["auth_methods"], !methods.is_empty());
+                for method in methods {
+                    match method.as_ref() {
+                        "password" | "v3password" => {
+                            check_required(report, auth_type, &["username", "user_id"], user);
+                            check_required(
+                                report,
+                                auth_type,
+                                &["password"],
+                                auth.has_secret("password"),
+                            );
+                        }
+                        "totp" | "v3totp" => {
+                            check_required(report, auth_type, &["username", "user_id"], user);
+                            check_required(
+                                report,
+                                auth_type,
+                                &["passcode"],
+                                auth.has_secret("passcode"),
+                            );
+                        }
+                        "token" | "v3token" => {
+                            check_required(report, auth_type, &["token"], auth.has_secret("token"))
+                        }
+                        other => report.error(
+                            "auth.auth_methods",
+                            format!("unsupported authentication method {}", other),
+                        ),
+                    }
+                }
+            }
+            "none" => {
+                check_required(report, auth_type, &["endpoint"], auth.endpoint.is_some());
+            }
+            "http_basic" => {
+                check_required(report, auth_type, &["endpoint"], auth.endpoint.is_some());
+                check_required(report, auth_type, &["username"], auth.username.is_some());
+                check_required(
+                    report,
+                    auth_type,
+                    &["password"],
+                    auth.has_secret("password"),
+                );
+            }
+            "admin_token" => {
+                check_required(report, auth_type, &["endpoint"], auth.endpoint.is_some());
+                check_required(report, auth_type, &["token"], auth.has_secret("token"));
+            }
+            _ => {}
+        }
+
+        if let Err(e) = self.scope() {
+            report.error("auth.system_scope", e.to_string());
+        }
+        let pairs = [
+            ("user_id", &auth.user_id, "username", &auth.username),
+            (
+                "project_id",
+                &auth.project_id,
+                "project_name",
+                &auth.project_name,
+            ),
+            (
+                "domain_id",
+                &auth.domain_id,
+                "domain_name",
+                &auth.domain_name,
+            ),
+            (
+                "user_domain_id",
+                &auth.user_domain_id,
+                "user_domain_name",
+                &auth.user_domain_name,
+            ),
+            (
+                "project_domain_id",
+                &auth.project_domain_id,
+                "project_domain_name",
+                &auth.project_domain_name,
+            ),
+        ];
+        for (id_key, id, name_key, name) in pairs.iter() {
+            if id.is_some() && name.is_some() {
+                report.warning(
+                    format!("auth.{}", name_key),
+                    format!("ignored since {} is set", id_key),
+                );
+            }
+        }
+    }
+
+    fn validate_regions(&self, report: &mut ValidationReport) {
+        let mut seen = HashSet::new();
+        for (index, region) in self.regions.iter().enumerate() {
+            let path = format!("regions[{}]", index);
+            if !seen.insert(region.name()) {
+                report.warning(path.as_str(), format!("duplicate region {}", region.name()));
+            }
+            if let Region::WithValues {
+                values: Some(values),
+                ..
+            } = region
+            {
+                if let Err(e) = serde_yaml::from_value::<CloudConfig>(values.clone()) {
+                    report.error(format!("{}.values", path), e.to_string());
+                }
+            }
+        }
+    }
+}
+
+/// Find the keys of a cloud that cannot be parsed.
+fn parse_errors(cloud: &Value, report: &mut ValidationReport) {
+    let mapping = match cloud {
+        Value::Mapping(mapping) => mapping,
+        _ => {
+            report.error("", "a cloud must be a mapping");
+            return;
+        }
+    };
+
+    for (key, value) in mapping {
+        let name = match key.as_str() {
+            Some(name) => name,
+            None => {
+                report.error(format!("{:?}", key), "keys must be strings");
+                continue;
+            }
+        };
+
+        if let (Some("auth"), Value::Mapping(auth)) = (key.as_str(), value) {
+            for (auth_key, auth_value) in auth {
+                let mut single = Mapping::new();
+                let _ = single.insert(auth_key.clone(), auth_value.clone());
+                if let Err(e) = serde_yaml::from_value::<AuthConfig>(Value::Mapping(single)) {
+                    report.error(
+                        format!("auth.{}", auth_key.as_str().unwrap_or("?")),
+                        e.to_string(),
+                    );
+                }
+            }
+            continue;
+        }
+
+        let mut single = Mapping::new();
+        let _ = single.insert(key.clone(), value.clone());
+        if let Err(e) = serde_yaml::from_value::<CloudConfig>(Value::Mapping(single)) {
+            report.error(name, e.to_string());
+        }
+    }
+}
+
+impl ConfigFiles {
+    fn validate(&self, name: &str) -> Result<ValidationReport, super::Error> {
+        let cloud = self.raw_cloud(name)?;
+        let prefix = format!("clouds.{}", name);
+
+        let resolved = match resolve_profile(cloud.clone(), self.public.as_ref()) {
+            Ok(resolved) => resolved,
+            Err(e) => {
+                let mut report = ValidationReport::default();
+                report.error("profile", e.to_string());
+                return Ok(report.with_prefix(&prefix));
+            }
+        };
+
+        let report = match serde_yaml::from_value::<CloudConfig>(resolved.clone()) {
+            Ok(config) => config.validate(),
+            Err(_) => {
+                let mut report = ValidationReport::default();
+                parse_errors(&resolved, &mut report);
+                if report.issues.is_empty() {
+                    report.error("", "cannot parse the configuration");
+                }
+                report
+            }
+        };
+        Ok(report.with_prefix(&prefix))
+    }
+}
+
+/// Check the configuration of a cloud from `clouds.yaml` without connecting to it.
+///
+/// All found issues are reported at once, see [CloudConfig::validate](struct.CloudConfig.html#method.validate)
+/// for the checks done. An error is only returned if the configuration files cannot be read or
+/// the cloud does not exist.
+pub fn validate<S: AsRef<str>>(cloud_name: S) -> Result<ValidationReport, super::Error> {
+    ConfigFiles::find()?.validate(cloud_name.as_ref())
+}
+
+/// Check the configuration of a cloud from the given file without connecting to it.
+///
+/// See [validate](fn.validate.html) for details.
+pub fn validate_file<P, S>(path: P, cloud_name: S) -> Result<ValidationReport, super::Error>
+where
+    P: AsRef<std::path::Path>,
+    S: AsRef<str>,
+{
+    ConfigFiles::load(path.as_ref())?.validate(cloud_name.as_ref())
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+
+    use super::super::CloudConfig;
+    use super::{validate_file, Severity, ValidationReport};
+
+    fn report(yaml: &str) -> ValidationReport {
+        serde_yaml::from_str::<CloudConfig>(yaml)
+            .unwrap()
+            .validate()
+    }
+
+    fn paths(report: &ValidationReport, severity: Severity) -> Vec<&str> {
+        report.issues_with(severity).map(|i| i.path()).collect()
+    }
+
+    #[test]
+    fn test_valid() {
+        let report = report(
+            "
+auth:
+  auth_url: http://127.0.0.1/identity
+  username: admin
+  password_env: OS_PASSWORD
+  project_name: admin
+region_name: RegionOne
+baremetal_api_version: '1.42'
+compute_endpoint_override: http://127.0.0.1/compute
+",
+        );
+        assert!(report.is_valid());
+        assert!(report.issues().is_empty());
+    }
+
+    #[test]
+    fn test_all_issues() {
+        let report = report(
+            "
+auth_type: v3applicationcredential
+auth:
+  auth_url: not a URL
+  application_credential_name: cred
+  project_name: admin
+  password_vault: secret
+cacert: /nonexistent/ca.crt
+key: /nonexistent/client.key
+identity_api_version: 2
+baremetal_api_version: latest
+compute_endpoint_override: ftp://127.0.0.1/compute
+regions:
+  - RegionOne
+  - RegionOne
+unknown_key: value
+",
+        );
+        assert!(!report.is_valid());
+        assert_eq!(
+            paths(&report, Severity::Error),
+            vec![
+                "auth.application_credential_secret",
+                "auth.username",
+                "auth.auth_url",
+                "cacert",
+                "key",
+                "key",
+                "identity_api_version",
+                "baremetal_api_version",
+                "compute_endpoint_override",
+            ]
+        );
+        assert_eq!(
+            paths(&report, Severity::Warning),
+            vec!["unknown_key", "auth.password_vault", "auth", "regions[1]"]
+        );
+    }
+
+    #[cfg(not(feature = "rustls"))]
+    #[test]
+    fn test_client_certificate_without_rustls() {
+        let report = report(
+            "
+auth_type: none
+auth:
+  endpoint: http://127.0.0.1/baremetal
+cert: /dev/null
+",
+        );
+        assert!(!report.is_valid());
+        assert_eq!(paths(&report, Severity::Error), vec!["cert"]);
+    }
+
+    #[test]
+    fn test_scope_conflicts() {
+        let report = report(
+            "
+auth:
+  auth_url: http://127.0.0.1/identity
+  user_id: 1234
+  username: admin
+  password: pa$$w0rd
+  project_name: admin
+  system_scope: all
+",
+        );
+        assert_eq!(paths(&report, Severity::Error), vec!["auth.system_scope"]);
+        assert_eq!(paths(&report, Severity::Warning), vec!["auth.username"]);
+    }
+
+    #[test]
+    fn test_unsupported_auth_type() {
+        let report = report(
+            "
+auth_type: magic
+auth:
+  auth_url: http://127.0.0.1/identity
+  spell: abracadabra
+",
+        );
+        assert_eq!(paths(&report, Severity::Error), vec!["auth_type"]);
+        assert!(paths(&report, Severity::Warning).is_empty());
+    }
+
+    #[test]
+    fn test_multifactor() {
+        let report = report(
+            "
+auth_type: v3multifactor
+auth:
+  auth_url: http://127.0.0.1/identity
+  username: admin
+  auth_methods: [v3password, magic]
+",
+        );
+        assert_eq!(
+            paths(&report, Severity::Error),
+            vec!["auth.password", "auth.auth_methods"]
+        );
+    }
+
+    #[test]
+    fn test_validate_file() {
+        let dir = std::env::temp_dir().join(format!("osauth-validate-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("clouds.yaml");
+        fs::write(
+            &path,
+            "
+clouds:
+  broken:
+    auth:
+      auth_url: http://127.0.0.1/identity
+      username: [admin]
+    verify: maybe
+  profiled:
+    profile: nope
+  valid:
+    auth_type: none
+    auth:
+      endpoint: http://127.0.0.1/baremetal
+",
+        )
+        .unwrap();
+
+        let report = validate_file(&path, "broken").unwrap();
+        assert_eq!(
+            paths(&report, Severity::Error),
+            vec!["clouds.broken.auth.username", "clouds.broken.verify"]
+        );
+        assert!(report.to_string().starts_with("error: clouds.broken."));
+
+        let report = validate_file(&path, "profiled").unwrap();
+        assert_eq!(
+            paths(&report, Severity::Error),
+            vec!["clouds.profiled.profile"]
+        );
+
+        assert!(validate_file(&path, "valid").unwrap().is_valid());
+        assert!(validate_file(&path, "nope").is_err());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -118,6 +118,12 @@ impl Error {
         self.kind
     }
 
+    /// HTTP status code (if any).
+    #[inline]
+    pub fn status(&self) -> Option<StatusCode> {
+        self.status
+    }
+
     /// Helper - error of kind EndpointNotFound.
     pub(crate) fn new_endpoint_not_found<D: fmt::Display>(service_type: D) -> Error {
         Error::new(
@@ -0,0 +1,132 @@
+// Copyright 2019 Dmitry Tantsur <divius.inside@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Health tracking of endpoints for failover.
+
+use std::collections::HashMap;
+use std::sync::RwLock;
+use std::time::{Duration, Instant};
+
+use log::{debug, warn};
+use reqwest::r#async::Response;
+use reqwest::{Error as HttpClientError, StatusCode, Url};
+
+/// Default time before a failed endpoint is tried again.
+pub const DEFAULT_COOLDOWN: Duration = Duration::from_secs(60);
+
+/// Failures of endpoints.
+#[derive(Debug, Default)]
+pub struct EndpointHealth {
+    failures: RwLock<HashMap<Url, Instant>>,
+}
+
+impl EndpointHealth {
+    /// Record a failure of the endpoint.
+    pub fn mark_failed(&self, endpoint: &Url) {
+        warn!("Endpoint {} is unavailable", endpoint);
+        let mut guard = self.failures.write().expect("Health lock is poisoned");
+        let _ = guard.insert(endpoint.clone(), Instant::now());
+    }
+
+    /// Record a successful access to the endpoint.
+    pub fn mark_healthy(&self, endpoint: &Url) {
+        let mut guard = self.failures.write().expect("Health lock is poisoned");
+        if guard.remove(endpoint).is_some() {
+            debug!("Endpoint {} is available again", endpoint);
+        }
+    }
+
+    /// Whether the endpoint has not failed during the cool-down time.
+    #[cfg(test)]
+    pub fn is_available(&self, endpoint: &Url, cooldown: Duration) -> bool {
+        let guard = self.failures.read().expect("Health lock is poisoned");
+        is_available(guard.get(endpoint), cooldown)
+    }
+
+    /// Order endpoints for trying them.
+    ///
+    /// Available endpoints come first in their original order, followed by the endpoints that
+    /// failed during the cool-down time, the least recently failed first.
+    pub fn order(&self, endpoints: Vec<Url>, cooldown: Duration) -> Vec<Url> {
+        let guard = self.failures.read().expect("Health lock is poisoned");
+        let (available, mut failed): (Vec<_>, Vec<_>) = endpoints
+            .into_iter()
+            .partition(|endpoint| is_available(guard.get(endpoint), cooldown));
+        failed.sort_by_key(|endpoint| guard.get(endpoint).cloned());
+        available.into_iter().chain(failed).collect()
+    }
+}
+
+#[inline]
+fn is_available(failed_at: Option<&Instant>, cooldown: Duration) -> bool {
+    failed_at
+        .map(|failed_at| failed_at.elapsed() >= cooldown)
+        .unwrap_or(true)
+}
+
+#[inline]
+fn is_unavailable_status(status: StatusCode) -> bool {
+    status == StatusCode::BAD_GATEWAY || status == StatusCode::SERVICE_UNAVAILABLE
+}
+
+/// Whether the result of sending a request means that the endpoint is unavailable.
+///
+/// This is the case for connection errors and HTTP 502 and 503.
+pub fn is_unavailable(result: &Result<Response, HttpClientError>) -> bool {
+    match result {
+        Ok(resp) => is_unavailable_status(resp.status()),
+        Err(e) => match e.status() {
+            Some(status) => is_unavailable_status(status),
+            None => !e.is_serialization() && !e.is_redirect(),
+        },
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use reqwest::Url;
+
+    use super::EndpointHealth;
+
+    #[test]
+    fn test_order() {
+        let urls = ["http://a/", "http://b/", "http://c/"]
+            .iter()
+            .map(|s| Url::parse(s).unwrap())
+            .collect::<Vec<_>>();
+        let health = EndpointHealth::default();
+        let cooldown = Duration::from_secs(60);
+        assert_eq!(health.order(urls.clone(), cooldown), urls);
+
+        health.mark_failed(&urls[1]);
+        health.mark_failed(&urls[0]);
+        assert!(!health.is_available(&urls[0], cooldown));
+        assert!(health.is_available(&urls[2], cooldown));
+        assert_eq!(
+            health.order(urls.clone(), cooldown),
+            vec![urls[2].clone(), urls[1].clone(), urls[0].clone()]
+        );
+
+        assert!(health.is_available(&urls[0], Duration::from_secs(0)));
+        assert_eq!(health.order(urls.clone(), Duration::from_secs(0)), urls);
+
+        health.mark_healthy(&urls[0]);
+        assert_eq!(
+            health.order(urls.clone(), cooldown),
+            vec![urls[0].clone(), urls[2].clone(), urls[1].clone()]
+        );
+    }
+}
@@ -0,0 +1,219 @@
+// Copyright 2019 Dmitry Tantsur <divius.inside@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Application credential authentication.
+
+use reqwest::r#async::Client;
+use reqwest::IntoUrl;
+
+use super::super::{Error, SecretSource};
+use super::internal::Internal;
+use super::protocol;
+use super::IdOrName;
+
+/// Application credential authentication using Identity API V3.
+///
+/// An application credential is identified either by its ID:
+///
+/// ```rust,no_run
+/// let auth = osauth::identity::ApplicationCredential::new(
+///     "https://cloud.local/identity",
+///     "423f19a4ac1e4f48bbb4180756e6eb6c",
+///     "rEaqvJka48mpv",
+/// )
+/// .expect("Invalid auth_url");
+///
+/// let session = osauth::Session::new(auth);
+/// ```
+///
+/// or by its name together with the owning user:
+///
+/// ```rust,no_run
+/// use osauth::identity::IdOrName;
+///
+/// let auth = osauth::identity::ApplicationCredential::new_with_name(
+///     "https://cloud.local/identity",
+///     "monitoring",
+///     "rEaqvJka48mpv",
+///     IdOrName::Name("admin".to_string()),
+///     Some(IdOrName::Name("Default".to_string())),
+/// )
+/// .expect("Invalid auth_url");
+/// ```
+///
+/// Application credentials are always scoped to the project they were created in, so no scope can
+/// be set.
+#[derive(Clone, Debug)]
+pub struct ApplicationCredential {
+    inner: Internal,
+}
+
+identity_common!(ApplicationCredential);
+
+impl ApplicationCredential {
+    /// Create an application credential authentication using the credential ID.
+    pub fn new<U, S1, S2>(auth_url: U, id: S1, secret: S2) -> Result<ApplicationCredential, Error>
+    where
+        U: IntoUrl,
+        S1: Into<String>,
+        S2: Into<String>,
+    {
+        ApplicationCredential::new_with_client(auth_url, Client::new(), id, secret)
+    }
+
+    /// Create an application credential authentication with the secret from the given source.
+    pub fn new_with_secret<U, S>(
+        auth_url: U,
+        id: S,
+        secret: &SecretSource,
+    ) -> Result<ApplicationCredential, Error>
+    where
+        U: IntoUrl,
+        S: Into<String>,
+    {
+        ApplicationCredential::new(auth_url, id, secret.resolve()?)
+    }
+
+    /// Create an application credential authentication with the provided HTTP client.
+    pub fn new_with_client<U, S1, S2>(
+        auth_url: U,
+        client: Client,
+        id: S1,
+        secret: S2,
+    ) -> Result<ApplicationCredential, Error>
+    where
+        U: IntoUrl,
+        S1: Into<String>,
+        S2: Into<String>,
+    {
+        ApplicationCredential::with_credential(
+            auth_url,
+            client,
+            protocol::ApplicationCredential {
+                id: IdOrName::Id(id.into()),
+                secret: secret.into().into(),
+                user: None,
+            },
+        )
+    }
+
+    /// Create an application credential authentication using the credential name.
+    ///
+    /// Credential names are only unique per user, so the user (and optionally its domain) has to
+    /// be provided.
+    pub fn new_with_name<U, S1, S2>(
+        auth_url: U,
+        name: S1,
+        secret: S2,
+        user: IdOrName,
+        user_domain: Option<IdOrName>,
+    ) -> Result<ApplicationCredential, Error>
+    where
+        U: IntoUrl,
+        S1: Into<String>,
+        S2: Into<String>,
+    {
+        ApplicationCredential::new_with_name_and_client(
+            auth_url,
+            Client::new(),
+            name,
+            secret,
+            user,
+            user_domain,
+        )
+    }
+
+    /// Create an application credential authentication using the credential name and the provided
+    /// HTTP client.
+    pub fn new_with_name_and_client<U, S1, S2>(
+        auth_url: U,
+        client: Client,
+        name: S1,
+        secret: S2,
+        user: IdOrName,
+        user_domain: Option<IdOrName>,
+    ) -> Result<ApplicationCredential, Error>
+    where
+        U: IntoUrl,
+        S1: Into<String>,
+        S2: Into<String>,
+    {
+        ApplicationCredential::with_credential(
+            auth_url,
+            client,
+            protocol::ApplicationCredential {
+                id: IdOrName::Name(name.into()),
+                secret: secret.into().into(),
+                user: Some(protocol::User {
+                    user,
+                    domain: user_domain,
+                }),
+            },
+        )
+    }
+
+    fn with_credential<U: IntoUrl>(
+        auth_url: U,
+        client: Client,
+        credential: protocol::ApplicationCredential,
+    ) -> Result<ApplicationCredential, Error> {
+        let identity =
+            protocol::Identity::new(protocol::AuthMethod::ApplicationCredential(credential));
+        Ok(ApplicationCredential {
+            inner: Internal::new(client, auth_url.into_url()?, identity),
+        })
+    }
+
+    /// Application credential name or ID.
+    #[inline]
+    pub fn application_credential(&self) -> &IdOrName {
+        match self.inner.identity().methods[0] {
+            protocol::AuthMethod::ApplicationCredential(ref cred) => &cred.id,
+            _ => unreachable!(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::{IdOrName, Identity};
+    use super::ApplicationCredential;
+
+    #[test]
+    fn test_application_credential_create() {
+        let id = ApplicationCredential::new("http://127.0.0.1:8080/identity", "abcdef", "secret")
+            .unwrap();
+        assert_eq!(id.auth_url().to_string(), "http://127.0.0.1:8080/identity");
+        assert_eq!(
+            id.application_credential(),
+            &IdOrName::Id("abcdef".to_string())
+        );
+    }
+
+    #[test]
+    fn test_application_credential_create_with_name() {
+        let id = ApplicationCredential::new_with_name(
+            "http://127.0.0.1:8080/identity",
+            "monitoring",
+            "secret",
+            IdOrName::Name("admin".to_string()),
+            None,
+        )
+        .unwrap();
+        assert_eq!(
+            id.application_credential(),
+            &IdOrName::Name("monitoring".to_string())
+        );
+    }
+}
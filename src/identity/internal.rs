@@ -0,0 +1,341 @@
+// Copyright 2019 Dmitry Tantsur <divius.inside@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Internal implementation shared by all Identity authentication methods.
+
+use std::collections::hash_map::DefaultHasher;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
+
+use chrono::{Duration, Local};
+use futures::future;
+use futures::prelude::*;
+use log::{debug, error, trace, warn};
+use reqwest::r#async::{Client, RequestBuilder, Response};
+use reqwest::{Method, Url};
+
+use super::super::cache::ValueCache;
+use super::super::catalog::{self, Catalog, CatalogRecord, Endpoint, EndpointFilter};
+use super::super::secret::SecretString;
+use super::super::{request, Error, ErrorKind};
+use super::protocol;
+
+const MISSING_SUBJECT_HEADER: &str = "Missing X-Subject-Token header";
+const INVALID_SUBJECT_HEADER: &str = "Invalid X-Subject-Token header";
+// Required validity time in minutes. Here we refresh the token if it expires
+// in 10 minutes or less.
+const TOKEN_MIN_VALIDITY: i64 = 10;
+
+/// Plain authentication token without additional details.
+#[derive(Clone)]
+struct Token {
+    value: SecretString,
+    body: protocol::Token,
+}
+
+impl fmt::Debug for Token {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut hasher = DefaultHasher::new();
+        self.value.as_str().hash(&mut hasher);
+        write!(
+            f,
+            "Token {{ value: hash({}), body: {:?} }}",
+            hasher.finish(),
+            self.body
+        )
+    }
+}
+
+/// Token fetching and caching logic common for all Identity methods.
+#[derive(Clone, Debug)]
+pub struct Internal {
+    client: Client,
+    auth_url: Url,
+    body: protocol::AuthRoot,
+    token_endpoint: String,
+    region: Option<String>,
+    cached_token: Arc<ValueCache<Token>>,
+    endpoint_interface: String,
+}
+
+impl Internal {
+    /// Create a new internal implementation.
+    pub fn new(client: Client, auth_url: Url, identity: protocol::Identity) -> Internal {
+        // TODO: more robust logic?
+        let token_endpoint = if auth_url.path().ends_with("/v3") {
+            format!("{}/auth/tokens", auth_url)
+        } else {
+            format!("{}/v3/auth/tokens", auth_url)
+        };
+        Internal {
+            client,
+            auth_url,
+            body: protocol::AuthRoot {
+                auth: protocol::Auth {
+                    identity,
+                    scope: None,
+                },
+            },
+            token_endpoint,
+            region: None,
+            cached_token: Arc::new(ValueCache::default()),
+            endpoint_interface: "public".to_string(),
+        }
+    }
+
+    /// Reference to the auth URL.
+    #[inline]
+    pub fn auth_url(&self) -> &Url {
+        &self.auth_url
+    }
+
+    /// The default endpoint interface.
+    #[inline]
+    pub fn default_endpoint_interface(&self) -> &String {
+        &self.endpoint_interface
+    }
+
+    /// Authentication identity.
+    #[inline]
+    pub fn identity(&self) -> &protocol::Identity {
+        &self.body.auth.identity
+    }
+
+    /// Authentication scope.
+    #[inline]
+    pub fn scope(&self) -> Option<&protocol::Scope> {
+        self.body.auth.scope.as_ref()
+    }
+
+    /// Region used with this authentication (if any).
+    #[inline]
+    pub fn region(&self) -> Option<String> {
+        self.region.clone()
+    }
+
+    /// Set the default endpoint interface to use.
+    #[inline]
+    pub fn set_default_endpoint_interface<S>(&mut self, endpoint_interface: S)
+    where
+        S: Into<String>,
+    {
+        self.endpoint_interface = endpoint_interface.into();
+    }
+
+    /// Set the HTTP client.
+    #[inline]
+    pub fn set_client(&mut self, client: Client) {
+        self.client = client;
+    }
+
+    /// Set a region for this authentication method.
+    #[inline]
+    pub fn set_region<S>(&mut self, region: S)
+    where
+        S: Into<String>,
+    {
+        self.region = Some(region.into());
+    }
+
+    /// Add an authentication method.
+    #[inline]
+    pub fn add_method(&mut self, method: protocol::AuthMethod) {
+        self.body.auth.identity.methods.push(method);
+    }
+
+    /// Set the authentication scope.
+    #[inline]
+    pub fn set_scope(&mut self, scope: protocol::Scope) {
+        self.body.auth.scope = Some(scope);
+    }
+
+    #[inline]
+    #[cfg(test)]
+    pub fn token_endpoint(&self) -> &str {
+        &self.token_endpoint
+    }
+
+    fn do_refresh(&self, force: bool) -> impl Future<Item = (), Error = Error> {
+        if !force && self.cached_token.validate(token_alive) {
+            future::Either::A(future::ok(()))
+        } else {
+            let cached_token = Arc::clone(&self.cached_token);
+            future::Either::B(
+                self.client
+                    .post(&self.token_endpoint)
+                    .json(&self.body)
+                    .send()
+                    .then(request::check)
+                    .and_then(token_from_response)
+                    .map(move |token| {
+                        cached_token.set(token.clone());
+                    }),
+            )
+        }
+    }
+
+    #[inline]
+    fn get_token(&self) -> impl Future<Item = String, Error = Error> {
+        let cached_token = Arc::clone(&self.cached_token);
+        self.do_refresh(false).map(move |()| {
+            cached_token
+                .extract(|t| t.value.as_str().to_string())
+                .unwrap()
+        })
+    }
+
+    #[inline]
+    fn get_catalog(&self) -> impl Future<Item = Vec<CatalogRecord>, Error = Error> {
+        let cached_token = Arc::clone(&self.cached_token);
+        self.do_refresh(false)
+            .map(move |()| cached_token.extract(|t| t.body.catalog.clone()).unwrap())
+    }
+
+    /// Get the service catalog.
+    #[inline]
+    pub fn catalog(&self) -> Box<dyn Future<Item = Catalog, Error = Error> + Send> {
+        Box::new(self.get_catalog().map(Catalog::new))
+    }
+
+    /// Create an authenticated request.
+    pub fn request(
+        &self,
+        method: Method,
+        url: Url,
+    ) -> Box<dyn Future<Item = RequestBuilder, Error = Error> + Send> {
+        // NOTE(dtantsur): this uses the fact that Client is implemented via Arc.
+        let client = self.client.clone();
+        Box::new(
+            self.get_token()
+                .map(move |token| client.request(method, url).header("x-auth-token", token)),
+        )
+    }
+
+    /// Get a URL for the requested service.
+    ///
+    /// Uses the region and the default endpoint interface of this authentication if the filter
+    /// does not specify them.
+    pub fn get_endpoint(
+        &self,
+        service_type: String,
+        mut filter: EndpointFilter,
+    ) -> Box<dyn Future<Item = Url, Error = Error> + Send> {
+        filter.fill_defaults(&self.endpoint_interface, self.region.as_deref());
+        debug!(
+            "Requesting a catalog endpoint for service '{}' with {:?}",
+            service_type, filter
+        );
+        Box::new(
+            self.get_catalog()
+                .and_then(move |cat| filter.find_url(&cat, &service_type)),
+        )
+    }
+
+    /// Get all URLs for the requested service.
+    ///
+    /// Uses the region and the default endpoint interface of this authentication if the filter
+    /// does not specify them.
+    pub fn get_endpoints(
+        &self,
+        service_type: String,
+        mut filter: EndpointFilter,
+    ) -> Box<dyn Future<Item = Vec<Url>, Error = Error> + Send> {
+        filter.fill_defaults(&self.endpoint_interface, self.region.as_deref());
+        debug!(
+            "Requesting all catalog endpoints for service '{}' with {:?}",
+            service_type, filter
+        );
+        Box::new(
+            self.get_catalog()
+                .and_then(move |cat| filter.find_urls(&cat, &service_type)),
+        )
+    }
+
+    /// Refresh the cached token and service catalog.
+    #[inline]
+    pub fn refresh(&self) -> Box<dyn Future<Item = (), Error = Error> + Send> {
+        Box::new(self.do_refresh(true))
+    }
+}
+
+#[inline]
+fn token_alive(value: &Token) -> bool {
+    let validity_time_left = value.body.expires_at.signed_duration_since(Local::now());
+    trace!("Token is valid for {:?}", validity_time_left);
+    validity_time_left > Duration::minutes(TOKEN_MIN_VALIDITY)
+}
+
+fn token_from_response(mut resp: Response) -> impl Future<Item = Token, Error = Error> {
+    let value = match resp.headers().get("x-subject-token") {
+        Some(hdr) => match hdr.to_str() {
+            Ok(s) => s.to_string(),
+            Err(e) => {
+                error!(
+                    "Invalid X-Subject-Token {:?} received from {}: {}",
+                    hdr,
+                    resp.url(),
+                    e
+                );
+                return future::Either::A(future::err(Error::new(
+                    ErrorKind::InvalidResponse,
+                    INVALID_SUBJECT_HEADER,
+                )));
+            }
+        },
+        None => {
+            error!("No X-Subject-Token header received from {}", resp.url());
+            return future::Either::A(future::err(Error::new(
+                ErrorKind::InvalidResponse,
+                MISSING_SUBJECT_HEADER,
+            )));
+        }
+    };
+
+    future::Either::B(
+        resp.json::<protocol::TokenRoot>()
+            .from_err()
+            .map(move |mut root| {
+                debug!(
+                    "Received a token from {} expiring at {}",
+                    resp.url(),
+                    root.token.expires_at
+                );
+                trace!("Received catalog: {:?}", root.token.catalog);
+                let token = &mut root.token;
+                match token.project {
+                    Some(ref project) => {
+                        catalog::resolve_templates(&mut token.catalog, &project.id)
+                    }
+                    None => {
+                        let templated = token
+                            .catalog
+                            .iter()
+                            .flat_map(|svc| svc.endpoints.iter())
+                            .any(Endpoint::is_templated);
+                        if templated {
+                            warn!(
+                                "The catalog has project templates, but the token \
+                                 is not scoped to a project"
+                            );
+                        }
+                    }
+                }
+                Token {
+                    value: value.into(),
+                    body: root.token,
+                }
+            }),
+    )
+}
@@ -0,0 +1,279 @@
+// Copyright 2019 Dmitry Tantsur <divius.inside@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Authentication using Identity API v3.
+//!
+//! Supports the following authentication methods:
+//! * [Password](struct.Password.html) - user name (or ID) and password.
+//! * [Token](struct.Token.html) - an existing token.
+//! * [ApplicationCredential](struct.ApplicationCredential.html) - application credential ID (or
+//!   name) and secret.
+//! * [MultiFactor](struct.MultiFactor.html) - a combination of several methods, e.g. a password
+//!   and a TOTP passcode.
+//!
+//! Identity API v2 is not and will not be supported.
+
+use reqwest::Url;
+
+pub use osproto::identity::IdOrName;
+
+/// Implement the parts common for all Identity authentication types.
+macro_rules! identity_common {
+    ($cls:ident) => {
+        impl $crate::identity::Identity for $cls {
+            fn auth_url(&self) -> &reqwest::Url {
+                self.inner.auth_url()
+            }
+        }
+
+        impl $cls {
+            /// The default endpoint interface.
+            #[inline]
+            pub fn default_endpoint_interface(&self) -> &String {
+                self.inner.default_endpoint_interface()
+            }
+
+            /// Set the default endpoint interface to use.
+            #[inline]
+            pub fn set_default_endpoint_interface<S>(&mut self, endpoint_interface: S)
+            where
+                S: Into<String>,
+            {
+                self.inner
+                    .set_default_endpoint_interface(endpoint_interface);
+            }
+
+            /// Set the HTTP client to use for authentication and requests.
+            #[inline]
+            pub fn set_client(&mut self, client: reqwest::r#async::Client) {
+                self.inner.set_client(client);
+            }
+
+            /// Set a region for this authentication method.
+            #[inline]
+            pub fn set_region<S>(&mut self, region: S)
+            where
+                S: Into<String>,
+            {
+                self.inner.set_region(region);
+            }
+
+            /// Convert this authentication into one using the given endpoint interface.
+            #[inline]
+            pub fn with_default_endpoint_interface<S>(mut self, endpoint_interface: S) -> Self
+            where
+                S: Into<String>,
+            {
+                self.set_default_endpoint_interface(endpoint_interface);
+                self
+            }
+
+            /// Use the given HTTP client for authentication and requests.
+            #[inline]
+            pub fn with_client(mut self, client: reqwest::r#async::Client) -> Self {
+                self.set_client(client);
+                self
+            }
+
+            /// Set a region for this authentication method.
+            #[inline]
+            pub fn with_region<S>(mut self, region: S) -> Self
+            where
+                S: Into<String>,
+            {
+                self.set_region(region);
+                self
+            }
+        }
+
+        impl $crate::AuthType for $cls {
+            /// Get region.
+            fn region(&self) -> Option<String> {
+                self.inner.region()
+            }
+
+            /// Create an authenticated request.
+            fn request(
+                &self,
+                method: reqwest::Method,
+                url: reqwest::Url,
+            ) -> Box<
+                dyn futures::Future<Item = reqwest::r#async::RequestBuilder, Error = $crate::Error>
+                    + Send,
+            > {
+                self.inner.request(method, url)
+            }
+
+            /// Get a URL for the requested service.
+            fn get_endpoint(
+                &self,
+                service_type: String,
+                endpoint_interface: Option<String>,
+            ) -> Box<dyn futures::Future<Item = reqwest::Url, Error = $crate::Error> + Send> {
+                let mut filter = $crate::EndpointFilter::new();
+                if let Some(endpoint_interface) = endpoint_interface {
+                    filter.set_interfaces(vec![endpoint_interface]);
+                }
+                self.inner.get_endpoint(service_type, filter)
+            }
+
+            /// Get a URL for the requested service using the filter.
+            fn find_endpoint(
+                &self,
+                service_type: String,
+                filter: $crate::EndpointFilter,
+            ) -> Box<dyn futures::Future<Item = reqwest::Url, Error = $crate::Error> + Send> {
+                self.inner.get_endpoint(service_type, filter)
+            }
+
+            /// Get all URLs for the requested service using the filter.
+            fn find_endpoints(
+                &self,
+                service_type: String,
+                filter: $crate::EndpointFilter,
+            ) -> Box<dyn futures::Future<Item = Vec<reqwest::Url>, Error = $crate::Error> + Send>
+            {
+                self.inner.get_endpoints(service_type, filter)
+            }
+
+            /// Get the service catalog.
+            fn get_catalog(
+                &self,
+            ) -> Box<
+                dyn futures::Future<Item = $crate::catalog::Catalog, Error = $crate::Error> + Send,
+            > {
+                self.inner.catalog()
+            }
+
+            /// Refresh the cached token and service catalog.
+            fn refresh(&self) -> Box<dyn futures::Future<Item = (), Error = $crate::Error> + Send> {
+                self.inner.refresh()
+            }
+
+            /// Copy the authentication with the given HTTP client.
+            fn clone_with_client(
+                &self,
+                client: reqwest::r#async::Client,
+            ) -> Option<Box<dyn $crate::AuthType>> {
+                Some(Box::new(self.clone().with_client(client)))
+            }
+        }
+    };
+}
+
+/// Implement scoping for Identity authentication types that support it.
+macro_rules! identity_scope {
+    ($cls:ident) => {
+        impl $cls {
+            /// Project name or ID (if project scoped).
+            #[inline]
+            pub fn project(&self) -> Option<&$crate::identity::IdOrName> {
+                match self.inner.scope() {
+                    Some($crate::identity::protocol::Scope::Project(ref prj)) => Some(&prj.project),
+                    _ => None,
+                }
+            }
+
+            /// Scope authentication to the given project.
+            ///
+            /// This is required in the most cases.
+            pub fn set_project_scope<S1, S2>(&mut self, project_name: S1, project_domain_name: S2)
+            where
+                S1: Into<String>,
+                S2: Into<String>,
+            {
+                self.set_scope($crate::identity::Scope::Project {
+                    project: $crate::identity::IdOrName::Name(project_name.into()),
+                    domain: Some($crate::identity::IdOrName::Name(project_domain_name.into())),
+                });
+            }
+
+            /// Set the authentication scope.
+            #[inline]
+            pub fn set_scope(&mut self, scope: $crate::identity::Scope) {
+                self.inner.set_scope(scope.into());
+            }
+
+            /// Scope authentication to the given project.
+            #[inline]
+            pub fn with_project_scope<S1, S2>(
+                mut self,
+                project_name: S1,
+                project_domain_name: S2,
+            ) -> Self
+            where
+                S1: Into<String>,
+                S2: Into<String>,
+            {
+                self.set_project_scope(project_name, project_domain_name);
+                self
+            }
+
+            /// Set the authentication scope.
+            #[inline]
+            pub fn with_scope(mut self, scope: $crate::identity::Scope) -> Self {
+                self.set_scope(scope);
+                self
+            }
+        }
+    };
+}
+
+mod application_credential;
+mod internal;
+mod multifactor;
+mod password;
+pub(crate) mod protocol;
+mod token;
+
+pub use self::application_credential::ApplicationCredential;
+pub use self::multifactor::MultiFactor;
+pub use self::password::Password;
+pub use self::token::Token;
+
+/// Authentication scope.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Scope {
+    /// A project, optionally with its domain.
+    ///
+    /// The domain is required if the project is identified by its name.
+    Project {
+        /// Project name or ID.
+        project: IdOrName,
+        /// Project domain name or ID.
+        domain: Option<IdOrName>,
+    },
+    /// A domain.
+    Domain(IdOrName),
+    /// The whole deployment (system scope).
+    System,
+}
+
+impl From<Scope> for protocol::Scope {
+    fn from(value: Scope) -> protocol::Scope {
+        match value {
+            Scope::Project { project, domain } => {
+                protocol::Scope::Project(protocol::Project { project, domain })
+            }
+            Scope::Domain(domain) => protocol::Scope::Domain(domain),
+            Scope::System => protocol::Scope::System,
+        }
+    }
+}
+
+/// Generic trait for authentication using Identity API V3.
+pub trait Identity {
+    /// Get a reference to the auth URL.
+    fn auth_url(&self) -> &Url;
+}
@@ -0,0 +1,181 @@
+// Copyright 2019 Dmitry Tantsur <divius.inside@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Multi-factor authentication.
+
+use reqwest::r#async::Client;
+use reqwest::IntoUrl;
+
+use super::super::Error;
+use super::internal::Internal;
+use super::protocol;
+use super::IdOrName;
+
+/// Multi-factor authentication using Identity API V3.
+///
+/// Combines several authentication methods in one request, for example a password and a TOTP
+/// passcode:
+///
+/// ```rust,no_run
+/// use osauth::identity::IdOrName;
+///
+/// let user = IdOrName::Name("admin".to_string());
+/// let domain = Some(IdOrName::Name("Default".to_string()));
+/// let auth = osauth::identity::MultiFactor::new("https://cloud.local/identity")
+///     .expect("Invalid auth_url")
+///     .with_password(user.clone(), "pa$$w0rd", domain.clone())
+///     .with_totp(user, "123456", domain)
+///     .with_project_scope("project1", "Default");
+///
+/// let session = osauth::Session::new(auth);
+/// ```
+///
+/// Note that a TOTP passcode can only be used once, so the authentication cannot be renewed when
+/// the token expires.
+#[derive(Clone, Debug)]
+pub struct MultiFactor {
+    inner: Internal,
+}
+
+identity_common!(MultiFactor);
+identity_scope!(MultiFactor);
+
+impl MultiFactor {
+    /// Create a multi-factor authentication without any methods.
+    pub fn new<U>(auth_url: U) -> Result<MultiFactor, Error>
+    where
+        U: IntoUrl,
+    {
+        MultiFactor::new_with_client(auth_url, Client::new())
+    }
+
+    /// Create a multi-factor authentication with the provided HTTP client.
+    pub fn new_with_client<U>(auth_url: U, client: Client) -> Result<MultiFactor, Error>
+    where
+        U: IntoUrl,
+    {
+        let identity = protocol::Identity {
+            methods: Vec::new(),
+        };
+        Ok(MultiFactor {
+            inner: Internal::new(client, auth_url.into_url()?, identity),
+        })
+    }
+
+    /// Names of the authentication methods in use.
+    pub fn methods(&self) -> Vec<&'static str> {
+        self.inner
+            .identity()
+            .methods
+            .iter()
+            .map(protocol::AuthMethod::name)
+            .collect()
+    }
+
+    /// Add password authentication.
+    pub fn add_password<S>(&mut self, user: IdOrName, password: S, user_domain: Option<IdOrName>)
+    where
+        S: Into<String>,
+    {
+        self.inner
+            .add_method(protocol::AuthMethod::Password(protocol::UserAndPassword {
+                user,
+                password: password.into().into(),
+                domain: user_domain,
+            }));
+    }
+
+    /// Add TOTP passcode authentication.
+    pub fn add_totp<S>(&mut self, user: IdOrName, passcode: S, user_domain: Option<IdOrName>)
+    where
+        S: Into<String>,
+    {
+        self.inner
+            .add_method(protocol::AuthMethod::Totp(protocol::UserAndPasscode {
+                user,
+                passcode: passcode.into().into(),
+                domain: user_domain,
+            }));
+    }
+
+    /// Add token authentication.
+    pub fn add_token<S>(&mut self, token: S)
+    where
+        S: Into<String>,
+    {
+        self.inner
+            .add_method(protocol::AuthMethod::Token(token.into().into()));
+    }
+
+    /// Add password authentication.
+    #[inline]
+    pub fn with_password<S>(
+        mut self,
+        user: IdOrName,
+        password: S,
+        user_domain: Option<IdOrName>,
+    ) -> Self
+    where
+        S: Into<String>,
+    {
+        self.add_password(user, password, user_domain);
+        self
+    }
+
+    /// Add TOTP passcode authentication.
+    #[inline]
+    pub fn with_totp<S>(
+        mut self,
+        user: IdOrName,
+        passcode: S,
+        user_domain: Option<IdOrName>,
+    ) -> Self
+    where
+        S: Into<String>,
+    {
+        self.add_totp(user, passcode, user_domain);
+        self
+    }
+
+    /// Add token authentication.
+    #[inline]
+    pub fn with_token<S>(mut self, token: S) -> Self
+    where
+        S: Into<String>,
+    {
+        self.add_token(token);
+        self
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::IdOrName;
+    use super::MultiFactor;
+
+    #[test]
+    fn test_multifactor_create() {
+        let user = IdOrName::Name("admin".to_string());
+        let id = MultiFactor::new("http://127.0.0.1:8080/identity")
+            .unwrap()
+            .with_password(user.clone(), "pa$$w0rd", None)
+            .with_totp(user, "123456", None)
+            .with_project_scope("cool project", "example.com");
+        assert_eq!(id.methods(), vec!["password", "totp"]);
+        assert_eq!(
+            id.project(),
+            Some(&IdOrName::Name("cool project".to_string()))
+        );
+    }
+}
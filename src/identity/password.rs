@@ -0,0 +1,315 @@
+// Copyright 2019 Dmitry Tantsur <divius.inside@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Password authentication.
+
+use reqwest::r#async::Client;
+use reqwest::IntoUrl;
+
+use super::super::{Error, SecretSource};
+use super::internal::Internal;
+use super::protocol;
+use super::IdOrName;
+
+/// Password authentication using Identity API V3.
+///
+/// For any Identity authentication you need to know `auth_url`, which is an authentication endpoint
+/// of the Identity service. For the Password authentication you also need:
+/// 1. User name and password.
+/// 2. Domain of the user.
+/// 3. Name of the project to use.
+/// 4. Domain of the project.
+///
+/// Start with creating a `Password` object using [new](#method.new), then add a project scope
+/// with [with_project_scope](#method.with_project_scope):
+///
+/// ```rust,no_run
+/// let auth = osauth::identity::Password::new(
+///     "https://cloud.local/identity",
+///     "admin",
+///     "pa$$w0rd",
+///     "Default"
+/// )
+/// .expect("Invalid auth_url")
+/// .with_project_scope("project1", "Default");
+///
+/// let session = osauth::Session::new(auth);
+/// ```
+///
+/// If your cloud has several regions, pick one using [with_region](#method.with_region):
+///
+/// ```rust,no_run
+/// let auth = osauth::identity::Password::new(
+///     "https://cloud.local/identity",
+///     "admin",
+///     "pa$$w0rd",
+///     "Default"
+/// )
+/// .expect("Invalid auth_url")
+/// .with_project_scope("project1", "Default")
+/// .with_region("US-East");
+///
+/// let session = osauth::Session::new(auth);
+/// ```
+///
+/// By default, the `public` endpoint interface is used. If you would prefer to default to another
+/// one, you can set it with
+/// [with_default_endpoint_interface](#method.with_default_endpoint_interface).
+///
+/// ```rust,no_run
+/// let auth = osauth::identity::Password::new(
+///     "https://cloud.local/identity",
+///     "admin",
+///     "pa$$w0rd",
+///     "Default"
+/// )
+/// .expect("Invalid auth_url")
+/// .with_project_scope("project1", "Default")
+/// .with_default_endpoint_interface("internal");
+/// ```
+///
+/// Use [new_with_user](#method.new_with_user) and [with_scope](#method.with_scope) if you need to
+/// identify the user, the project or the domains by their IDs:
+///
+/// ```rust,no_run
+/// use osauth::identity::{IdOrName, Scope};
+///
+/// let auth = osauth::identity::Password::new_with_user(
+///     "https://cloud.local/identity",
+///     IdOrName::Id("ee4dfb6e5540447cb3741905149d9b6e".to_string()),
+///     "pa$$w0rd",
+///     None
+/// )
+/// .expect("Invalid auth_url")
+/// .with_scope(Scope::Project {
+///     project: IdOrName::Id("a7b5c3d3b1a24a0ab4c8f8e8b9cc1234".to_string()),
+///     domain: None,
+/// });
+/// ```
+///
+/// The authentication token is cached while it's still valid or until
+/// [refresh](../trait.AuthType.html#tymethod.refresh) is called.
+#[derive(Clone, Debug)]
+pub struct Password {
+    inner: Internal,
+}
+
+identity_common!(Password);
+identity_scope!(Password);
+
+impl Password {
+    /// Create a password authentication.
+    pub fn new<U, S1, S2, S3>(
+        auth_url: U,
+        user_name: S1,
+        password: S2,
+        user_domain_name: S3,
+    ) -> Result<Password, Error>
+    where
+        U: IntoUrl,
+        S1: Into<String>,
+        S2: Into<String>,
+        S3: Into<String>,
+    {
+        Password::new_with_client(
+            auth_url,
+            Client::new(),
+            user_name,
+            password,
+            user_domain_name,
+        )
+    }
+
+    /// Create a password authentication with the provided HTTP client.
+    pub fn new_with_client<U, S1, S2, S3>(
+        auth_url: U,
+        client: Client,
+        user_name: S1,
+        password: S2,
+        user_domain_name: S3,
+    ) -> Result<Password, Error>
+    where
+        U: IntoUrl,
+        S1: Into<String>,
+        S2: Into<String>,
+        S3: Into<String>,
+    {
+        let pw = protocol::UserAndPassword {
+            user: IdOrName::Name(user_name.into()),
+            password: password.into().into(),
+            domain: Some(IdOrName::Name(user_domain_name.into())),
+        };
+        let identity = protocol::Identity::new(protocol::AuthMethod::Password(pw));
+        Ok(Password {
+            inner: Internal::new(client, auth_url.into_url()?, identity),
+        })
+    }
+
+    /// Create a password authentication with the password from the given source.
+    pub fn new_with_secret<U, S1, S2>(
+        auth_url: U,
+        user_name: S1,
+        password: &SecretSource,
+        user_domain_name: S2,
+    ) -> Result<Password, Error>
+    where
+        U: IntoUrl,
+        S1: Into<String>,
+        S2: Into<String>,
+    {
+        Password::new(auth_url, user_name, password.resolve()?, user_domain_name)
+    }
+
+    /// Create a password authentication with a user name or ID.
+    ///
+    /// The user domain is only required when the user is identified by its name.
+    pub fn new_with_user<U, S>(
+        auth_url: U,
+        user: IdOrName,
+        password: S,
+        user_domain: Option<IdOrName>,
+    ) -> Result<Password, Error>
+    where
+        U: IntoUrl,
+        S: Into<String>,
+    {
+        let pw = protocol::UserAndPassword {
+            user,
+            password: password.into().into(),
+            domain: user_domain,
+        };
+        let identity = protocol::Identity::new(protocol::AuthMethod::Password(pw));
+        Ok(Password {
+            inner: Internal::new(Client::new(), auth_url.into_url()?, identity),
+        })
+    }
+
+    /// User name.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the user is identified by its ID.
+    #[inline]
+    #[deprecated(since = "0.2.3", note = "Use user in preparation for user ID support.")]
+    pub fn user_name(&self) -> &String {
+        match *self.user() {
+            IdOrName::Name(ref user) => user,
+            IdOrName::Id(..) => panic!("The user is identified by its ID, use user() instead"),
+        }
+    }
+
+    /// User name or ID.
+    #[inline]
+    pub fn user(&self) -> &IdOrName {
+        match self.inner.identity().methods[0] {
+            protocol::AuthMethod::Password(ref pw) => &pw.user,
+            _ => unreachable!(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    #![allow(unused_results)]
+
+    use super::super::super::AuthType;
+    use super::super::{IdOrName, Identity, Scope};
+    use super::Password;
+
+    #[test]
+    fn test_identity_new() {
+        let id = Password::new("http://127.0.0.1:8080/", "admin", "pa$$w0rd", "Default").unwrap();
+        let e = id.auth_url();
+        assert_eq!(e.scheme(), "http");
+        assert_eq!(e.host_str().unwrap(), "127.0.0.1");
+        assert_eq!(e.port().unwrap(), 8080u16);
+        assert_eq!(e.path(), "/");
+        assert_eq!(id.user(), &IdOrName::Name("admin".to_string()));
+    }
+
+    #[test]
+    fn test_identity_new_invalid() {
+        Password::new("http://127.0.0.1 8080/", "admin", "pa$$w0rd", "Default")
+            .err()
+            .unwrap();
+    }
+
+    #[test]
+    fn test_identity_create() {
+        let id = Password::new(
+            "http://127.0.0.1:8080/identity",
+            "user",
+            "pa$$w0rd",
+            "example.com",
+        )
+        .unwrap()
+        .with_project_scope("cool project", "example.com");
+        assert_eq!(id.auth_url().to_string(), "http://127.0.0.1:8080/identity");
+        assert_eq!(id.user(), &IdOrName::Name("user".to_string()));
+        assert_eq!(
+            id.project(),
+            Some(&IdOrName::Name("cool project".to_string()))
+        );
+        assert_eq!(
+            id.inner.token_endpoint(),
+            "http://127.0.0.1:8080/identity/v3/auth/tokens"
+        );
+        assert_eq!(id.region(), None);
+    }
+
+    #[test]
+    fn test_identity_debug() {
+        let id = Password::new(
+            "http://127.0.0.1:8080/identity",
+            "user",
+            "pa$$w0rd",
+            "example.com",
+        )
+        .unwrap();
+        let debug = format!("{:?}", id);
+        assert!(debug.contains("user"));
+        assert!(!debug.contains("pa$$w0rd"));
+    }
+
+    #[test]
+    fn test_identity_create_with_ids() {
+        let id = Password::new_with_user(
+            "http://127.0.0.1:8080/identity",
+            IdOrName::Id("abcdef".to_string()),
+            "pa$$w0rd",
+            None,
+        )
+        .unwrap()
+        .with_scope(Scope::Project {
+            project: IdOrName::Id("12345".to_string()),
+            domain: None,
+        });
+        assert_eq!(id.user(), &IdOrName::Id("abcdef".to_string()));
+        assert_eq!(id.project(), Some(&IdOrName::Id("12345".to_string())));
+    }
+
+    #[test]
+    fn test_identity_system_scope() {
+        let id = Password::new(
+            "http://127.0.0.1:8080/identity",
+            "admin",
+            "pa$$w0rd",
+            "Default",
+        )
+        .unwrap()
+        .with_project_scope("cool project", "example.com")
+        .with_scope(Scope::System);
+        assert_eq!(id.project(), None);
+    }
+}
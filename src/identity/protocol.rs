@@ -0,0 +1,330 @@
+// Copyright 2019 Dmitry Tantsur <divius.inside@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Identity V3 JSON structures for authentication requests.
+//!
+//! The `osproto` crate only supports single-method password and token requests, the structures
+//! here extend them with application credentials, TOTP and multi-method authentication. Tokens
+//! use the catalog structures from this crate, which include service names and endpoint IDs.
+
+use chrono::{DateTime, FixedOffset};
+use serde::ser::SerializeStruct;
+use serde::{Deserialize, Serialize, Serializer};
+
+pub use osproto::identity::{IdOrName, Project, Scope};
+
+use super::super::catalog::CatalogRecord;
+use super::super::secret::SecretString;
+
+/// A project the token is scoped to.
+#[derive(Clone, Debug, Deserialize)]
+pub struct TokenProject {
+    pub id: String,
+}
+
+/// An authentication token with embedded catalog.
+#[derive(Clone, Debug, Deserialize)]
+pub struct Token {
+    pub expires_at: DateTime<FixedOffset>,
+    #[serde(default)]
+    pub catalog: Vec<CatalogRecord>,
+    #[serde(default)]
+    pub project: Option<TokenProject>,
+}
+
+/// A token response root.
+#[derive(Clone, Debug, Deserialize)]
+pub struct TokenRoot {
+    pub token: Token,
+}
+
+/// A reference to a user with an optional domain.
+#[derive(Clone, Debug, Serialize)]
+pub struct User {
+    #[serde(flatten)]
+    pub user: IdOrName,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub domain: Option<IdOrName>,
+}
+
+/// User and password.
+#[derive(Clone, Debug, Serialize)]
+pub struct UserAndPassword {
+    #[serde(flatten)]
+    pub user: IdOrName,
+    pub password: SecretString,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub domain: Option<IdOrName>,
+}
+
+/// User and TOTP passcode.
+#[derive(Clone, Debug, Serialize)]
+pub struct UserAndPasscode {
+    #[serde(flatten)]
+    pub user: IdOrName,
+    pub passcode: SecretString,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub domain: Option<IdOrName>,
+}
+
+/// Application credential.
+#[derive(Clone, Debug, Serialize)]
+pub struct ApplicationCredential {
+    #[serde(flatten)]
+    pub id: IdOrName,
+    pub secret: SecretString,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub user: Option<User>,
+}
+
+/// A single authentication method.
+#[derive(Clone, Debug)]
+pub enum AuthMethod {
+    /// Authentication with a user and a password.
+    Password(UserAndPassword),
+    /// Authentication with a token.
+    Token(SecretString),
+    /// Authentication with an application credential.
+    ApplicationCredential(ApplicationCredential),
+    /// Authentication with a TOTP passcode.
+    Totp(UserAndPasscode),
+}
+
+/// Authentication identity consisting of one or more methods.
+#[derive(Clone, Debug)]
+pub struct Identity {
+    pub methods: Vec<AuthMethod>,
+}
+
+/// An authentication object.
+#[derive(Clone, Debug, Serialize)]
+pub struct Auth {
+    /// Authentication identity.
+    pub identity: Identity,
+    /// Authentication scope (if needed).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scope: Option<Scope>,
+}
+
+/// An authentication request root.
+#[derive(Clone, Debug, Serialize)]
+pub struct AuthRoot {
+    pub auth: Auth,
+}
+
+#[derive(Debug, Serialize)]
+struct UserWrapper<'a, T> {
+    user: &'a T,
+}
+
+#[derive(Debug, Serialize)]
+struct TokenAuth<'a> {
+    id: &'a str,
+}
+
+impl AuthMethod {
+    /// Name of the method as understood by the Identity service.
+    pub fn name(&self) -> &'static str {
+        match self {
+            AuthMethod::Password(..) => "password",
+            AuthMethod::Token(..) => "token",
+            AuthMethod::ApplicationCredential(..) => "application_credential",
+            AuthMethod::Totp(..) => "totp",
+        }
+    }
+}
+
+impl Identity {
+    /// Create an identity with one method.
+    #[inline]
+    pub fn new(method: AuthMethod) -> Identity {
+        Identity {
+            methods: vec![method],
+        }
+    }
+}
+
+impl Serialize for Identity {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let names = self
+            .methods
+            .iter()
+            .map(AuthMethod::name)
+            .collect::<Vec<_>>();
+        let mut inner = serializer.serialize_struct("Identity", self.methods.len() + 1)?;
+        inner.serialize_field("methods", &names)?;
+        for method in &self.methods {
+            match method {
+                AuthMethod::Password(ref user) => {
+                    inner.serialize_field("password", &UserWrapper { user })?
+                }
+                AuthMethod::Token(ref token) => {
+                    inner.serialize_field("token", &TokenAuth { id: token.as_str() })?
+                }
+                AuthMethod::ApplicationCredential(ref cred) => {
+                    inner.serialize_field("application_credential", cred)?
+                }
+                AuthMethod::Totp(ref user) => {
+                    inner.serialize_field("totp", &UserWrapper { user })?
+                }
+            }
+        }
+        inner.end()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::{self, Value};
+
+    use super::*;
+
+    fn compare<T: Serialize>(expected: &str, value: T) {
+        let expected: Value = serde_json::from_str(expected).unwrap();
+        let actual = serde_json::to_value(value).unwrap();
+        assert_eq!(expected, actual);
+    }
+
+    const APPLICATION_CREDENTIAL_ID: &str = r#"
+{
+    "auth": {
+        "identity": {
+            "methods": [
+                "application_credential"
+            ],
+            "application_credential": {
+                "id": "423f19a4ac1e4f48bbb4180756e6eb6c",
+                "secret": "rEaqvJka48mpv"
+            }
+        }
+    }
+}"#;
+
+    const APPLICATION_CREDENTIAL_NAME: &str = r#"
+{
+    "auth": {
+        "identity": {
+            "methods": [
+                "application_credential"
+            ],
+            "application_credential": {
+                "name": "monitoring",
+                "secret": "rEaqvJka48mpv",
+                "user": {
+                    "name": "admin",
+                    "domain": {
+                        "name": "Default"
+                    }
+                }
+            }
+        }
+    }
+}"#;
+
+    const PASSWORD_AND_TOTP: &str = r#"
+{
+    "auth": {
+        "identity": {
+            "methods": [
+                "password",
+                "totp"
+            ],
+            "password": {
+                "user": {
+                    "id": "ee4dfb6e5540447cb3741905149d9b6e",
+                    "password": "devstacker"
+                }
+            },
+            "totp": {
+                "user": {
+                    "id": "ee4dfb6e5540447cb3741905149d9b6e",
+                    "passcode": "123456"
+                }
+            }
+        },
+        "scope": {
+            "project": {
+                "name": "admin",
+                "domain": {
+                    "name": "Default"
+                }
+            }
+        }
+    }
+}"#;
+
+    #[test]
+    fn test_application_credential_id() {
+        let value = AuthRoot {
+            auth: Auth {
+                identity: Identity::new(AuthMethod::ApplicationCredential(ApplicationCredential {
+                    id: IdOrName::Id("423f19a4ac1e4f48bbb4180756e6eb6c".to_string()),
+                    secret: "rEaqvJka48mpv".to_string().into(),
+                    user: None,
+                })),
+                scope: None,
+            },
+        };
+        compare(APPLICATION_CREDENTIAL_ID, value);
+    }
+
+    #[test]
+    fn test_application_credential_name() {
+        let value = AuthRoot {
+            auth: Auth {
+                identity: Identity::new(AuthMethod::ApplicationCredential(ApplicationCredential {
+                    id: IdOrName::Name("monitoring".to_string()),
+                    secret: "rEaqvJka48mpv".to_string().into(),
+                    user: Some(User {
+                        user: IdOrName::Name("admin".to_string()),
+                        domain: Some(IdOrName::Name("Default".to_string())),
+                    }),
+                })),
+                scope: None,
+            },
+        };
+        compare(APPLICATION_CREDENTIAL_NAME, value);
+    }
+
+    #[test]
+    fn test_password_and_totp() {
+        let user = IdOrName::Id("ee4dfb6e5540447cb3741905149d9b6e".to_string());
+        let value = AuthRoot {
+            auth: Auth {
+                identity: Identity {
+                    methods: vec![
+                        AuthMethod::Password(UserAndPassword {
+                            user: user.clone(),
+                            password: "devstacker".to_string().into(),
+                            domain: None,
+                        }),
+                        AuthMethod::Totp(UserAndPasscode {
+                            user,
+                            passcode: "123456".to_string().into(),
+                            domain: None,
+                        }),
+                    ],
+                },
+                scope: Some(Scope::Project(Project {
+                    project: IdOrName::Name("admin".to_string()),
+                    domain: Some(IdOrName::Name("Default".to_string())),
+                })),
+            },
+        };
+        compare(PASSWORD_AND_TOTP, value);
+    }
+}
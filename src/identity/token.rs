@@ -0,0 +1,103 @@
+// Copyright 2019 Dmitry Tantsur <divius.inside@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Token authentication.
+
+use reqwest::r#async::Client;
+use reqwest::IntoUrl;
+
+use super::super::{Error, SecretSource};
+use super::internal::Internal;
+use super::protocol;
+
+/// Token authentication using Identity API V3.
+///
+/// An existing token is exchanged for a new one, which is usually used to change its scope:
+///
+/// ```rust,no_run
+/// let auth = osauth::identity::Token::new(
+///     "https://cloud.local/identity",
+///     "<a token>",
+/// )
+/// .expect("Invalid auth_url")
+/// .with_project_scope("project1", "Default");
+///
+/// let session = osauth::Session::new(auth);
+/// ```
+///
+/// The resulting token cannot outlive the original one.
+#[derive(Clone, Debug)]
+pub struct Token {
+    inner: Internal,
+}
+
+identity_common!(Token);
+identity_scope!(Token);
+
+impl Token {
+    /// Create a token authentication.
+    pub fn new<U, S>(auth_url: U, token: S) -> Result<Token, Error>
+    where
+        U: IntoUrl,
+        S: Into<String>,
+    {
+        Token::new_with_client(auth_url, Client::new(), token)
+    }
+
+    /// Create a token authentication with the token from the given source.
+    #[inline]
+    pub fn new_with_secret<U: IntoUrl>(auth_url: U, token: &SecretSource) -> Result<Token, Error> {
+        Token::new(auth_url, token.resolve()?)
+    }
+
+    /// Create a token authentication with the provided HTTP client.
+    pub fn new_with_client<U, S>(auth_url: U, client: Client, token: S) -> Result<Token, Error>
+    where
+        U: IntoUrl,
+        S: Into<String>,
+    {
+        let identity = protocol::Identity::new(protocol::AuthMethod::Token(token.into().into()));
+        Ok(Token {
+            inner: Internal::new(client, auth_url.into_url()?, identity),
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::super::AuthType;
+    use super::super::{IdOrName, Identity};
+    use super::Token;
+
+    #[test]
+    fn test_token_create() {
+        let id = Token::new("http://127.0.0.1:8080/identity/v3", "abcdef")
+            .unwrap()
+            .with_project_scope("cool project", "example.com")
+            .with_region("RegionOne");
+        assert_eq!(
+            id.auth_url().to_string(),
+            "http://127.0.0.1:8080/identity/v3"
+        );
+        assert_eq!(
+            id.project(),
+            Some(&IdOrName::Name("cool project".to_string()))
+        );
+        assert_eq!(
+            id.inner.token_endpoint(),
+            "http://127.0.0.1:8080/identity/v3/auth/tokens"
+        );
+        assert_eq!(id.region(), Some("RegionOne".to_string()));
+    }
+}
@@ -40,11 +40,16 @@
 //! Your entry point to the API is the [Session](struct.Session.html) structure. To create it you
 //! need an authentication type object first. It can be obtained by:
 //! * Using [Password](identity/struct.Password.html) authentication against the Identity service.
+//!   Other Identity methods are available in the [identity](identity/index.html) module.
 //! * Using [NoAuth](struct.NoAuth.html) authentication type, allowing access to standalone
 //!   services without authentication.
+//! * Using [StaticCatalog](struct.StaticCatalog.html) authentication type with a static token and
+//!   a service catalog loaded from a file, without calling the Identity service.
 //!
 //! A `Session` can be created directly by loading it:
-//! * From the `clouds.yaml` configuration file using [from_config](fn.from_config.html).
+//! * From the `clouds.yaml` configuration file using [from_config](fn.from_config.html) or
+//!   [from_config_file](fn.from_config_file.html). See the [config](config/index.html) module
+//!   for the supported authentication types.
 //! * From environment variables using [from_env](fn.from_env.html).
 //!
 //! See [Session](struct.Session.html) documentation for the details on using a `Session` for making
@@ -97,21 +102,36 @@ mod adapter;
 mod apiversion;
 mod auth;
 mod cache;
-mod catalog;
-mod config;
+pub mod catalog;
+pub mod config;
 mod error;
+mod health;
 pub mod identity;
 mod protocol;
+mod proxy;
 pub mod request;
+mod rewrite;
+mod secret;
+mod service_options;
 pub mod services;
 mod session;
 #[cfg(feature = "sync")]
 pub mod sync;
+mod tls;
 mod url;
 
+#[cfg(not(any(feature = "native-tls", feature = "rustls")))]
+compile_error!("Either the native-tls or the rustls feature must be enabled");
+
 pub use crate::adapter::Adapter;
 pub use crate::apiversion::ApiVersion;
-pub use crate::auth::{AuthType, NoAuth};
-pub use crate::config::{from_config, from_env};
+pub use crate::auth::{AdminToken, AuthType, BasicAuth, EndpointOverride, NoAuth, StaticCatalog};
+pub use crate::catalog::EndpointFilter;
+pub use crate::config::{from_config, from_config_file, from_env};
 pub use crate::error::{Error, ErrorKind};
+pub use crate::proxy::ProxyConfig;
+pub use crate::rewrite::{RewriteRule, RewriteRules};
+pub use crate::secret::SecretSource;
+pub use crate::service_options::ServiceOptions;
 pub use crate::session::Session;
+pub use crate::tls::TlsConfig;
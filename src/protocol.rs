@@ -16,20 +16,23 @@
 
 use std::convert::TryFrom;
 use std::sync::Arc;
+use std::time::Duration;
 
-use futures::future;
+use futures::future::{self, Loop};
 use futures::prelude::*;
 use log::{debug, trace, warn};
 use osproto::common::{Root, Version};
 use reqwest::{Method, Url};
 
+use super::health::{self, EndpointHealth};
 use super::request;
+use super::rewrite::RewriteRules;
 use super::services::ServiceType;
 use super::url;
 use super::{ApiVersion, AuthType, Error, ErrorKind};
 
 /// Information about API endpoint.
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct ServiceInfo {
     /// Root endpoint.
     pub root_url: Url,
@@ -64,16 +67,86 @@ impl TryFrom<Version> for ServiceInfo {
     }
 }
 
+/// Fetch the version root, the flag in the error is set if the endpoint is unavailable.
 #[inline]
 fn fetch_root(
     catalog_type: &'static str,
     endpoint: Url,
     auth: Arc<AuthType>,
-) -> impl Future<Item = Root, Error = Error> {
+) -> impl Future<Item = Root, Error = (Error, bool)> {
     debug!("Fetching {} service info from {}", catalog_type, endpoint);
 
     auth.request(Method::GET, endpoint)
-        .then(request::fetch_json)
+        .map_err(|e| (e, false))
+        .and_then(|builder| {
+            builder.send().then(|result| {
+                let unavailable = health::is_unavailable(&result);
+                request::check(result).map_err(move |e| (e, unavailable))
+            })
+        })
+        .and_then(|mut resp| resp.json().map_err(|e| (Error::from(e), false)))
+}
+
+/// Fetch the version root, trying the parent endpoint on HTTP 404.
+fn discover(
+    catalog_type: &'static str,
+    endpoint: Url,
+    auth: Arc<dyn AuthType>,
+) -> impl Future<Item = Root, Error = (Error, bool)> {
+    fetch_root(catalog_type, endpoint.clone(), auth.clone()).or_else(move |(e, unavailable)| {
+        if e.kind() == ErrorKind::ResourceNotFound {
+            if url::is_root(&endpoint) {
+                let err = Error::new_endpoint_not_found(catalog_type);
+                future::Either::A(future::err((err, false)))
+            } else {
+                debug!("Got HTTP 404 from {}, trying parent endpoint", endpoint);
+                future::Either::B(fetch_root(catalog_type, url::pop(endpoint, true), auth))
+            }
+        } else {
+            future::Either::A(future::err((e, unavailable)))
+        }
+    })
+}
+
+/// Keep the path suffix of the endpoint (e.g. a project ID) after the discovered root URL.
+///
+/// Some services, e.g. Block Storage, have a project ID after the version in their endpoints,
+/// while version discovery returns the root URL of the version without it.
+fn keep_suffix(mut root_url: Url, endpoint: &Url) -> Url {
+    let root: Vec<_> = root_url
+        .path_segments()
+        .map(|segments| segments.filter(|x| !x.is_empty()).collect())
+        .unwrap_or_default();
+    let original: Vec<_> = endpoint
+        .path_segments()
+        .map(|segments| segments.filter(|x| !x.is_empty()).collect())
+        .unwrap_or_default();
+
+    let suffix_start = if original.starts_with(&root) {
+        root.len()
+    } else {
+        // The root URL may have a different prefix, e.g. behind a proxy.
+        match root
+            .last()
+            .and_then(|version| original.iter().rposition(|x| x == version))
+        {
+            Some(index) => index + 1,
+            None => return root_url,
+        }
+    };
+
+    if suffix_start < original.len() {
+        let suffix = original[suffix_start..]
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>();
+        debug!(
+            "Keeping path suffix {:?} of {} after discovery",
+            suffix, endpoint
+        );
+        root_url = url::extend(root_url, suffix);
+    }
+    root_url
 }
 
 impl ServiceInfo {
@@ -124,60 +197,94 @@ impl ServiceInfo {
         }
     }
 
-    /// Generic code to extract a `ServiceInfo` from a URL.
+    /// Generic code to extract a `ServiceInfo` from one of the endpoints.
+    ///
+    /// The rewrite rules are applied both to the endpoints and to the discovered root URL.
+    /// Endpoints are tried in the order of their health, unavailable ones are marked as failed.
+    ///
+    /// Returns the endpoint that was used together with the information.
     pub fn fetch<Srv: ServiceType>(
         service: Srv,
-        endpoint: Url,
+        endpoints: Vec<Url>,
         auth: Arc<AuthType>,
-    ) -> impl Future<Item = ServiceInfo, Error = Error> {
+        rules: Arc<RewriteRules>,
+        health: Arc<EndpointHealth>,
+        cooldown: Duration,
+    ) -> impl Future<Item = (Url, ServiceInfo), Error = Error> {
+        let catalog_type = service.catalog_type();
+        let endpoints = match endpoints
+            .into_iter()
+            .map(|endpoint| rules.apply(catalog_type, endpoint))
+            .collect::<Result<Vec<_>, _>>()
+        {
+            Ok(endpoints) => health.order(endpoints, cooldown),
+            Err(e) => return future::Either::A(future::err(e)),
+        };
+        let mut remaining = endpoints.into_iter();
+        let first = match remaining.next() {
+            Some(endpoint) => endpoint,
+            None => {
+                return future::Either::A(future::err(Error::new_endpoint_not_found(catalog_type)))
+            }
+        };
+
         if !service.version_discovery_supported() {
             debug!(
                 "Service {} does not support version discovery, using {}",
-                service.catalog_type(),
-                endpoint
+                catalog_type, first
             );
-            return future::Either::A(future::ok(ServiceInfo {
-                root_url: endpoint,
+            let info = ServiceInfo {
+                root_url: first.clone(),
                 major_version: None,
                 current_version: None,
                 minimum_version: None,
-            }));
+            };
+            return future::Either::A(future::ok((first, info)));
         }
 
-        // Workaround for old version of Nova returning HTTP endpoints even if
-        // accessed via HTTP
-        let secure = endpoint.scheme() == "https";
-        let catalog_type = service.catalog_type();
-
         future::Either::B(
-            fetch_root(catalog_type, endpoint.clone(), auth.clone())
-                .or_else(move |e| {
-                    if e.kind() == ErrorKind::ResourceNotFound {
-                        if url::is_root(&endpoint) {
-                            let err = Error::new_endpoint_not_found(catalog_type);
-                            future::Either::A(future::err(err))
-                        } else {
-                            debug!("Got HTTP 404 from {}, trying parent endpoint", endpoint);
-                            future::Either::B(fetch_root(
-                                catalog_type,
-                                url::pop(endpoint, true),
-                                auth,
-                            ))
+            future::loop_fn((first, remaining), move |(endpoint, mut remaining)| {
+                let health = Arc::clone(&health);
+                discover(catalog_type, endpoint.clone(), Arc::clone(&auth)).then(move |result| {
+                    match result {
+                        Ok(root) => {
+                            health.mark_healthy(&endpoint);
+                            Ok(Loop::Break((endpoint, root)))
                         }
-                    } else {
-                        future::Either::A(future::err(e))
+                        Err((e, true)) => {
+                            health.mark_failed(&endpoint);
+                            match remaining.next() {
+                                Some(next) => {
+                                    warn!(
+                                        "Failing over from {} to {} for {} service: {}",
+                                        endpoint, next, catalog_type, e
+                                    );
+                                    Ok(Loop::Continue((next, remaining)))
+                                }
+                                None => Err(e),
+                            }
+                        }
+                        Err((e, false)) => Err(e),
                     }
                 })
-                .and_then(|root| ServiceInfo::from_root(root, service))
-                .map(move |mut info| {
-                    // Older Nova returns insecure URLs even for secure protocol.
-                    if secure && info.root_url.scheme() == "http" {
-                        info.root_url.set_scheme("https").unwrap();
-                    }
+            })
+            .and_then(|(endpoint, root)| {
+                ServiceInfo::from_root(root, service).map(|info| (endpoint, info))
+            })
+            .and_then(move |(endpoint, mut info)| {
+                // Older Nova returns insecure URLs even for secure protocol.
+                if endpoint.scheme() == "https"
+                    && rules.upgrade_insecure()
+                    && info.root_url.scheme() == "http"
+                {
+                    info.root_url.set_scheme("https").unwrap();
+                }
+                info.root_url = rules.apply(catalog_type, info.root_url)?;
+                info.root_url = keep_suffix(info.root_url, &endpoint);
 
-                    debug!("Received {:?} for {} service", info, catalog_type);
-                    info
-                }),
+                debug!("Received {:?} for {} service", info, catalog_type);
+                Ok((endpoint, info))
+            }),
         )
     }
 }
@@ -189,9 +296,9 @@ pub(crate) mod test {
     use osproto::common::{Link, Root, Version, XdotY};
     use reqwest::Url;
 
-    use super::super::services::ServiceType;
+    use super::super::services::{ServiceType, BLOCK_STORAGE};
     use super::super::{ApiVersion, ErrorKind};
-    use super::ServiceInfo;
+    use super::{keep_suffix, ServiceInfo};
 
     #[test]
     fn test_version_into_service_info() {
@@ -290,6 +397,31 @@ pub(crate) mod test {
         assert_eq!(err.kind(), ErrorKind::EndpointNotFound);
     }
 
+    #[test]
+    fn test_root_into_service_info_block_storage() {
+        let root = |major| Root::OneVersion {
+            version: Version {
+                id: XdotY(major, 0),
+                links: vec![Link {
+                    href: Url::parse(&format!("https://example.com/v{}", major)).unwrap(),
+                    rel: "self".to_string(),
+                }],
+                status: Some("CURRENT".to_string()),
+                version: None,
+                min_version: None,
+            },
+        };
+
+        let info = ServiceInfo::from_root(root(3), BLOCK_STORAGE).unwrap();
+        assert_eq!(info.major_version, Some(ApiVersion(3, 0)));
+
+        // A catalog with only volumev2 must not silently use API version 2.
+        let err = ServiceInfo::from_root(root(2), BLOCK_STORAGE)
+            .err()
+            .unwrap();
+        assert_eq!(err.kind(), ErrorKind::EndpointNotFound);
+    }
+
     #[test]
     fn test_root_into_service_info_versions() {
         let url = Url::parse("https://example.com/v1.2").unwrap();
@@ -375,4 +507,39 @@ pub(crate) mod test {
             .unwrap();
         assert_eq!(err.kind(), ErrorKind::EndpointNotFound);
     }
+
+    #[test]
+    fn test_keep_suffix() {
+        let cases = [
+            (
+                "https://host/volume/v3/",
+                "https://host/volume/v3/abcd",
+                "https://host/volume/v3/abcd",
+            ),
+            (
+                "http://internal:8776/v3/",
+                "https://host/volume/v3/abcd",
+                "http://internal:8776/v3/abcd",
+            ),
+            (
+                "https://host/compute/v2.1/",
+                "https://host/compute/v2.1",
+                "https://host/compute/v2.1/",
+            ),
+            (
+                "https://host/compute/v2.1/",
+                "https://host/compute",
+                "https://host/compute/v2.1/",
+            ),
+            (
+                "https://host:8776/v3/",
+                "https://host:8776/",
+                "https://host:8776/v3/",
+            ),
+        ];
+        for (root, endpoint, expected) in cases.iter() {
+            let result = keep_suffix(Url::parse(root).unwrap(), &Url::parse(endpoint).unwrap());
+            assert_eq!(result.as_str(), *expected);
+        }
+    }
 }
@@ -0,0 +1,489 @@
+// Copyright 2019 Dmitry Tantsur <divius.inside@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Rewriting of endpoint URLs.
+
+use log::debug;
+use regex::Regex;
+use reqwest::Url;
+
+use super::catalog;
+use super::{Error, ErrorKind};
+
+/// A rule to rewrite endpoint URLs.
+///
+/// A rule without a service type or a pattern applies to all URLs. A rule with both only applies
+/// to URLs matching both of them.
+///
+/// ```rust
+/// let rule = osauth::RewriteRule::new()
+///     .with_pattern(r"^https?://controller\.internal[:/]")
+///     .expect("Invalid regular expression")
+///     .with_scheme("https")
+///     .expect("Invalid scheme")
+///     .with_host("cloud.example.com")
+///     .expect("Invalid host")
+///     .with_port(13000);
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct RewriteRule {
+    service_type: Option<String>,
+    pattern: Option<Regex>,
+    scheme: Option<String>,
+    host: Option<String>,
+    port: Option<u16>,
+    path_prefix: Option<(String, String)>,
+}
+
+/// Rules to rewrite endpoint URLs.
+///
+/// The rules apply to endpoints from the service catalog (or endpoint overrides) and to root URLs
+/// returned by version discovery. All matching rules are applied in the order they were added.
+///
+/// Additionally, root URLs returned by version discovery are upgraded from `http` to `https` if
+/// the endpoint uses `https`, since some services (e.g. older Compute) return insecure URLs even
+/// when accessed via HTTPS.
+#[derive(Clone, Debug)]
+pub struct RewriteRules {
+    rules: Vec<RewriteRule>,
+    upgrade_insecure: bool,
+}
+
+fn normalize_path(path: &str) -> String {
+    format!("/{}", path.trim_matches('/'))
+}
+
+impl RewriteRule {
+    /// Create a rule that matches all URLs and does not change them.
+    #[inline]
+    pub fn new() -> RewriteRule {
+        RewriteRule::default()
+    }
+
+    /// Service type this rule is limited to (if any).
+    #[inline]
+    pub fn service_type(&self) -> Option<&str> {
+        self.service_type.as_deref()
+    }
+
+    /// Regular expression that URLs must match (if any).
+    #[inline]
+    pub fn pattern(&self) -> Option<&str> {
+        self.pattern.as_ref().map(Regex::as_str)
+    }
+
+    /// New scheme (if any).
+    #[inline]
+    pub fn scheme(&self) -> Option<&str> {
+        self.scheme.as_deref()
+    }
+
+    /// New host (if any).
+    #[inline]
+    pub fn host(&self) -> Option<&str> {
+        self.host.as_deref()
+    }
+
+    /// New port (if any).
+    #[inline]
+    pub fn port(&self) -> Option<u16> {
+        self.port
+    }
+
+    /// Path prefix and its replacement (if any).
+    #[inline]
+    pub fn path_prefix(&self) -> Option<(&str, &str)> {
+        self.path_prefix
+            .as_ref()
+            .map(|(old, new)| (old.as_str(), new.as_str()))
+    }
+
+    /// Whether the rule applies to the URL of the service type.
+    ///
+    /// Aliases of service types are resolved, e.g. a rule for `block-storage` also applies to
+    /// `volumev3`.
+    pub fn matches(&self, service_type: &str, url: &Url) -> bool {
+        if let Some(ref expected) = self.service_type {
+            let service_types = catalog::service_types();
+            let official = service_types
+                .official_type(service_type)
+                .unwrap_or(service_type);
+            if official != expected {
+                return false;
+            }
+        }
+
+        self.pattern
+            .as_ref()
+            .map(|pattern| pattern.is_match(url.as_str()))
+            .unwrap_or(true)
+    }
+
+    /// Apply the rule to the URL, regardless of whether it matches.
+    pub fn apply(&self, url: &Url) -> Result<Url, Error> {
+        let mut result = url.clone();
+
+        if let Some(ref scheme) = self.scheme {
+            result.set_scheme(scheme).map_err(|()| {
+                Error::new(
+                    ErrorKind::InvalidInput,
+                    format!("Cannot change scheme of {} to {}", url, scheme),
+                )
+            })?;
+        }
+
+        if let Some(ref host) = self.host {
+            result.set_host(Some(host)).map_err(|e| {
+                Error::new(
+                    ErrorKind::InvalidInput,
+                    format!("Cannot change host of {} to {}: {}", url, host, e),
+                )
+            })?;
+        }
+
+        if let Some(port) = self.port {
+            result.set_port(Some(port)).map_err(|()| {
+                Error::new(
+                    ErrorKind::InvalidInput,
+                    format!("Cannot change port of {} to {}", url, port),
+                )
+            })?;
+        }
+
+        if let Some((ref old, ref new)) = self.path_prefix {
+            let path = result.path().to_string();
+            let rest = if old == "/" {
+                Some(&path[..])
+            } else if path == *old {
+                Some("")
+            } else if path.starts_with(old.as_str()) && path[old.len()..].starts_with('/') {
+                Some(&path[old.len()..])
+            } else {
+                None
+            };
+
+            if let Some(rest) = rest {
+                let new_path = format!("{}{}", new.trim_end_matches('/'), rest);
+                result.set_path(if new_path.is_empty() { "/" } else { &new_path });
+            }
+        }
+
+        Ok(result)
+    }
+
+    /// Limit the rule to the service type.
+    pub fn set_service_type<S>(&mut self, service_type: S)
+    where
+        S: Into<String>,
+    {
+        let service_type = service_type.into();
+        self.service_type = Some(
+            catalog::service_types()
+                .official_type(&service_type)
+                .map(ToString::to_string)
+                .unwrap_or(service_type),
+        );
+    }
+
+    /// Limit the rule to URLs matching the regular expression.
+    pub fn set_pattern(&mut self, pattern: &str) -> Result<(), Error> {
+        let regex = Regex::new(pattern).map_err(|e| {
+            Error::new(
+                ErrorKind::InvalidInput,
+                format!("Invalid regular expression {}: {}", pattern, e),
+            )
+        })?;
+        self.pattern = Some(regex);
+        Ok(())
+    }
+
+    /// Replace the scheme of matching URLs.
+    ///
+    /// Only `http` and `https` are supported.
+    pub fn set_scheme<S>(&mut self, scheme: S) -> Result<(), Error>
+    where
+        S: Into<String>,
+    {
+        let scheme = scheme.into();
+        match scheme.as_str() {
+            "http" | "https" => {
+                self.scheme = Some(scheme);
+                Ok(())
+            }
+            other => Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("Unsupported scheme {}", other),
+            )),
+        }
+    }
+
+    /// Replace the host of matching URLs.
+    pub fn set_host<S>(&mut self, host: S) -> Result<(), Error>
+    where
+        S: Into<String>,
+    {
+        let host = host.into();
+        let mut test = Url::parse("http://localhost/").expect("BUG: invalid test URL");
+        test.set_host(Some(&host)).map_err(|e| {
+            Error::new(
+                ErrorKind::InvalidInput,
+                format!("Invalid host {}: {}", host, e),
+            )
+        })?;
+        self.host = Some(host);
+        Ok(())
+    }
+
+    /// Replace the port of matching URLs.
+    #[inline]
+    pub fn set_port(&mut self, port: u16) {
+        self.port = Some(port);
+    }
+
+    /// Replace the path prefix `old` of matching URLs with `new`.
+    ///
+    /// Only complete path segments are matched, e.g. `/compute` matches `/compute/v2.1`, but not
+    /// `/computer`.
+    pub fn set_path_prefix<S1, S2>(&mut self, old: S1, new: S2)
+    where
+        S1: AsRef<str>,
+        S2: AsRef<str>,
+    {
+        self.path_prefix = Some((normalize_path(old.as_ref()), normalize_path(new.as_ref())));
+    }
+
+    /// Limit the rule to the service type.
+    #[inline]
+    pub fn with_service_type<S>(mut self, service_type: S) -> Self
+    where
+        S: Into<String>,
+    {
+        self.set_service_type(service_type);
+        self
+    }
+
+    /// Limit the rule to URLs matching the regular expression.
+    #[inline]
+    pub fn with_pattern(mut self, pattern: &str) -> Result<Self, Error> {
+        self.set_pattern(pattern)?;
+        Ok(self)
+    }
+
+    /// Replace the scheme of matching URLs.
+    #[inline]
+    pub fn with_scheme<S>(mut self, scheme: S) -> Result<Self, Error>
+    where
+        S: Into<String>,
+    {
+        self.set_scheme(scheme)?;
+        Ok(self)
+    }
+
+    /// Replace the host of matching URLs.
+    #[inline]
+    pub fn with_host<S>(mut self, host: S) -> Result<Self, Error>
+    where
+        S: Into<String>,
+    {
+        self.set_host(host)?;
+        Ok(self)
+    }
+
+    /// Replace the port of matching URLs.
+    #[inline]
+    pub fn with_port(mut self, port: u16) -> Self {
+        self.set_port(port);
+        self
+    }
+
+    /// Replace the path prefix `old` of matching URLs with `new`.
+    #[inline]
+    pub fn with_path_prefix<S1, S2>(mut self, old: S1, new: S2) -> Self
+    where
+        S1: AsRef<str>,
+        S2: AsRef<str>,
+    {
+        self.set_path_prefix(old, new);
+        self
+    }
+}
+
+impl Default for RewriteRules {
+    fn default() -> RewriteRules {
+        RewriteRules {
+            rules: Vec::new(),
+            upgrade_insecure: true,
+        }
+    }
+}
+
+impl RewriteRules {
+    /// Create rules that only upgrade insecure URLs from version discovery.
+    #[inline]
+    pub fn new() -> RewriteRules {
+        RewriteRules::default()
+    }
+
+    /// Rules in the order of application.
+    #[inline]
+    pub fn rules(&self) -> &[RewriteRule] {
+        &self.rules
+    }
+
+    /// Whether insecure URLs from version discovery are upgraded to `https`.
+    #[inline]
+    pub fn upgrade_insecure(&self) -> bool {
+        self.upgrade_insecure
+    }
+
+    /// Apply all matching rules to the URL of the service type.
+    pub fn apply(&self, service_type: &str, url: Url) -> Result<Url, Error> {
+        let mut result = url;
+        for rule in self.rules.iter() {
+            if rule.matches(service_type, &result) {
+                let new_url = rule.apply(&result)?;
+                debug!(
+                    "Rewriting {} to {} for service {}",
+                    result, new_url, service_type
+                );
+                result = new_url;
+            }
+        }
+        Ok(result)
+    }
+
+    /// Add a rule to the end of the list.
+    #[inline]
+    pub fn push(&mut self, rule: RewriteRule) {
+        self.rules.push(rule);
+    }
+
+    /// Set whether insecure URLs from version discovery are upgraded to `https`.
+    #[inline]
+    pub fn set_upgrade_insecure(&mut self, value: bool) {
+        self.upgrade_insecure = value;
+    }
+
+    /// Add a rule to the end of the list.
+    #[inline]
+    pub fn with_rule(mut self, rule: RewriteRule) -> Self {
+        self.push(rule);
+        self
+    }
+
+    /// Set whether insecure URLs from version discovery are upgraded to `https`.
+    #[inline]
+    pub fn with_upgrade_insecure(mut self, value: bool) -> Self {
+        self.set_upgrade_insecure(value);
+        self
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use reqwest::Url;
+
+    use super::super::ErrorKind;
+    use super::{RewriteRule, RewriteRules};
+
+    #[test]
+    fn test_rule_matches() {
+        let url = Url::parse("http://controller.internal:8776/v3/abcd").unwrap();
+        assert!(RewriteRule::new().matches("compute", &url));
+
+        let rule = RewriteRule::new().with_service_type("volumev3");
+        assert!(rule.matches("block-storage", &url));
+        assert!(rule.matches("volume", &url));
+        assert!(!rule.matches("compute", &url));
+
+        let rule = RewriteRule::new()
+            .with_service_type("block-storage")
+            .with_pattern(r"^http://controller\.internal:")
+            .unwrap();
+        assert!(rule.matches("block-storage", &url));
+        assert!(!rule.matches("compute", &url));
+        let other = Url::parse("http://cloud.example.com:8776/v3/abcd").unwrap();
+        assert!(!rule.matches("block-storage", &other));
+    }
+
+    #[test]
+    fn test_rule_apply() {
+        let url = Url::parse("http://controller.internal:8774/compute/v2.1").unwrap();
+        let rule = RewriteRule::new()
+            .with_scheme("https")
+            .unwrap()
+            .with_host("cloud.example.com")
+            .unwrap()
+            .with_port(13000)
+            .with_path_prefix("/compute/", "/nova");
+        assert_eq!(
+            rule.apply(&url).unwrap().as_str(),
+            "https://cloud.example.com:13000/nova/v2.1"
+        );
+
+        let rule = RewriteRule::new().with_path_prefix("/comp", "/nova");
+        assert_eq!(rule.apply(&url).unwrap(), url);
+
+        let rule = RewriteRule::new().with_path_prefix("/", "/compute");
+        let url = Url::parse("http://controller.internal/v2.1/").unwrap();
+        assert_eq!(
+            rule.apply(&url).unwrap().as_str(),
+            "http://controller.internal/compute/v2.1/"
+        );
+
+        let rule = RewriteRule::new().with_path_prefix("/v2.1", "/");
+        assert_eq!(
+            rule.apply(&url).unwrap().as_str(),
+            "http://controller.internal/"
+        );
+    }
+
+    #[test]
+    fn test_rule_invalid() {
+        let err = RewriteRule::new().with_pattern("(").err().unwrap();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+        let err = RewriteRule::new().with_scheme("ftp").err().unwrap();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+        let err = RewriteRule::new().with_host("a b").err().unwrap();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn test_rules_apply_in_order() {
+        let rules = RewriteRules::new()
+            .with_rule(
+                RewriteRule::new()
+                    .with_pattern("controller")
+                    .unwrap()
+                    .with_host("gateway.local")
+                    .unwrap(),
+            )
+            .with_rule(
+                RewriteRule::new()
+                    .with_pattern("gateway")
+                    .unwrap()
+                    .with_port(8443)
+                    .with_scheme("https")
+                    .unwrap(),
+            )
+            .with_rule(RewriteRule::new().with_service_type("compute").with_port(1));
+        assert!(rules.upgrade_insecure());
+
+        let url = Url::parse("http://controller:6385/v1").unwrap();
+        assert_eq!(
+            rules.apply("baremetal", url).unwrap().as_str(),
+            "https://gateway.local:8443/v1"
+        );
+    }
+}
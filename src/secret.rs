@@ -0,0 +1,327 @@
+// Copyright 2019 Dmitry Tantsur <divius.inside@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Sources of secrets, such as passwords and tokens.
+
+use std::env;
+use std::fmt::{self, Debug};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use log::debug;
+use serde::{Serialize, Serializer};
+use zeroize::{Zeroize, Zeroizing};
+
+use super::{Error, ErrorKind};
+
+/// A source of a secret, such as a password or a token.
+///
+/// Authentication types that accept secrets have `new_with_secret` constructors:
+///
+/// ```rust,no_run
+/// let password = osauth::SecretSource::Command("vault read -field=password secret/cloud".into());
+///
+/// let auth = osauth::identity::Password::new_with_secret(
+///     "https://cloud.local/identity",
+///     "admin",
+///     &password,
+///     "Default"
+/// )
+/// .expect("Cannot create authentication");
+/// ```
+///
+/// Use [resolve](#method.resolve) to get the secret for other constructors.
+///
+/// In `clouds.yaml`, a secret key of the `auth` section (e.g. `password`) can be replaced with
+/// `<key>_env`, `<key>_file`, `<key>_command` or `<key>_keyring` (with `service` and `user`),
+/// see [AuthConfig::secret_source](config/struct.AuthConfig.html#method.secret_source).
+#[derive(Clone, PartialEq, Eq)]
+pub enum SecretSource {
+    /// The secret itself.
+    Inline(String),
+    /// Name of an environment variable with the secret.
+    Env(String),
+    /// Path to a file with the secret.
+    ///
+    /// On Unix the file must not be accessible by the group or other users.
+    File(PathBuf),
+    /// A shell command printing the secret.
+    Command(String),
+    /// An entry in the OS keyring, requires the `keyring` feature.
+    Keyring {
+        /// Service of the entry.
+        service: String,
+        /// User of the entry.
+        user: String,
+    },
+}
+
+impl Debug for SecretSource {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SecretSource::Inline(_) => f.debug_tuple("Inline").field(&"***").finish(),
+            SecretSource::Env(name) => f.debug_tuple("Env").field(name).finish(),
+            SecretSource::File(path) => f.debug_tuple("File").field(path).finish(),
+            SecretSource::Command(_) => f.debug_tuple("Command").field(&"***").finish(),
+            SecretSource::Keyring { service, user } => f
+                .debug_struct("Keyring")
+                .field("service", service)
+                .field("user", user)
+                .finish(),
+        }
+    }
+}
+
+impl Drop for SecretSource {
+    fn drop(&mut self) {
+        if let SecretSource::Inline(ref mut value) = self {
+            value.zeroize();
+        }
+    }
+}
+
+impl From<String> for SecretSource {
+    fn from(value: String) -> SecretSource {
+        SecretSource::Inline(value)
+    }
+}
+
+impl<'s> From<&'s str> for SecretSource {
+    fn from(value: &'s str) -> SecretSource {
+        SecretSource::Inline(value.to_string())
+    }
+}
+
+/// A string that is redacted in `Debug` output and zeroed when dropped.
+#[derive(Clone, Default, PartialEq, Eq)]
+pub(crate) struct SecretString(Zeroizing<String>);
+
+impl SecretString {
+    /// The secret value.
+    #[inline]
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Debug for SecretString {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("\"***\"")
+    }
+}
+
+impl From<String> for SecretString {
+    fn from(value: String) -> SecretString {
+        SecretString(Zeroizing::new(value))
+    }
+}
+
+impl Serialize for SecretString {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+fn strip_newline(mut value: String) -> String {
+    while value.ends_with('\n') || value.ends_with('\r') {
+        let _ = value.pop();
+    }
+    value
+}
+
+#[cfg(unix)]
+fn check_permissions(path: &Path) -> Result<(), Error> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let metadata = fs::metadata(path).map_err(|e| {
+        Error::new(
+            ErrorKind::InvalidConfig,
+            format!("Cannot access secret file {}: {}", path.display(), e),
+        )
+    })?;
+    if metadata.permissions().mode() & 0o077 != 0 {
+        return Err(Error::new(
+            ErrorKind::InvalidConfig,
+            format!(
+                "Secret file {} must not be accessible by other users",
+                path.display()
+            ),
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn check_permissions(_path: &Path) -> Result<(), Error> {
+    Ok(())
+}
+
+// The command itself may contain secrets (e.g. tokens), so it is never logged or reported.
+fn run_command(cmd: &str) -> Result<String, Error> {
+    debug!("Running a secret command");
+    let output = if cfg!(windows) {
+        Command::new("cmd").args(["/C", cmd]).output()
+    } else {
+        Command::new("sh").args(["-c", cmd]).output()
+    }
+    .map_err(|e| {
+        Error::new(
+            ErrorKind::InvalidConfig,
+            format!("Cannot run secret command: {}", e),
+        )
+    })?;
+
+    debug!("Secret command finished with {}", output.status);
+    if !output.status.success() {
+        return Err(Error::new(
+            ErrorKind::InvalidConfig,
+            format!("Secret command failed with {}", output.status),
+        ));
+    }
+
+    String::from_utf8(output.stdout)
+        .map(strip_newline)
+        .map_err(|_| {
+            Error::new(
+                ErrorKind::InvalidConfig,
+                "Secret command returned invalid UTF-8",
+            )
+        })
+}
+
+#[cfg(feature = "keyring")]
+fn read_keyring(service: &str, user: &str) -> Result<String, Error> {
+    keyring::Entry::new(service, user)
+        .and_then(|entry| entry.get_password())
+        .map_err(|e| {
+            Error::new(
+                ErrorKind::InvalidConfig,
+                format!(
+                    "Cannot read the keyring entry for {} in {}: {}",
+                    user, service, e
+                ),
+            )
+        })
+}
+
+#[cfg(not(feature = "keyring"))]
+fn read_keyring(_service: &str, _user: &str) -> Result<String, Error> {
+    Err(Error::new(
+        ErrorKind::InvalidConfig,
+        "Keyring secrets require the keyring feature",
+    ))
+}
+
+impl SecretSource {
+    /// Get the secret from this source.
+    ///
+    /// A trailing newline is removed from secrets read from files and commands.
+    #[inline]
+    pub fn resolve(&self) -> Result<String, Error> {
+        self.resolve_with_env(|name| env::var(name).ok())
+    }
+
+    /// Get the secret from this source, looking up environment variables using `lookup`.
+    pub(crate) fn resolve_with_env<F>(&self, lookup: F) -> Result<String, Error>
+    where
+        F: FnOnce(&str) -> Option<String>,
+    {
+        match self {
+            SecretSource::Inline(value) => Ok(value.clone()),
+            SecretSource::Env(name) => lookup(name).ok_or_else(|| {
+                Error::new(
+                    ErrorKind::InvalidConfig,
+                    format!("Environment variable {} with a secret is not set", name),
+                )
+            }),
+            SecretSource::File(path) => {
+                check_permissions(path)?;
+                fs::read_to_string(path).map(strip_newline).map_err(|e| {
+                    Error::new(
+                        ErrorKind::InvalidConfig,
+                        format!("Cannot read secret file {}: {}", path.display(), e),
+                    )
+                })
+            }
+            SecretSource::Command(cmd) => run_command(cmd),
+            SecretSource::Keyring { service, user } => read_keyring(service, user),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::env;
+    use std::fs;
+
+    use super::super::ErrorKind;
+    use super::{SecretSource, SecretString};
+
+    #[test]
+    fn test_inline_and_env() {
+        let source = SecretSource::from("pa$$w0rd");
+        assert_eq!(source.resolve().unwrap(), "pa$$w0rd");
+        assert!(!format!("{:?}", source).contains("pa$$w0rd"));
+
+        let lookup = |name: &str| match name {
+            "OSAUTH_TEST_SECRET" => Some("s3cr3t".to_string()),
+            _ => None,
+        };
+        let source = SecretSource::Env("OSAUTH_TEST_SECRET".to_string());
+        assert_eq!(source.resolve_with_env(lookup).unwrap(), "s3cr3t");
+
+        let source = SecretSource::Env("OSAUTH_TEST_SECRET_MISSING".to_string());
+        let err = source.resolve_with_env(lookup).err().unwrap();
+        assert_eq!(err.kind(), ErrorKind::InvalidConfig);
+    }
+
+    #[test]
+    fn test_secret_string() {
+        let secret = SecretString::from("pa$$w0rd".to_string());
+        assert_eq!(secret.as_str(), "pa$$w0rd");
+        assert_eq!(format!("{:?}", secret), "\"***\"");
+        assert_eq!(serde_json::to_string(&secret).unwrap(), "\"pa$$w0rd\"");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_file() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let path = env::temp_dir().join(format!("osauth-secret-{}", std::process::id()));
+        fs::write(&path, "s3cr3t\n").unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o644)).unwrap();
+        let source = SecretSource::File(path.clone());
+        let err = source.resolve().err().unwrap();
+        assert_eq!(err.kind(), ErrorKind::InvalidConfig);
+
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o600)).unwrap();
+        assert_eq!(source.resolve().unwrap(), "s3cr3t");
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_command() {
+        let source = SecretSource::Command("echo s3cr3t".to_string());
+        assert_eq!(source.resolve().unwrap(), "s3cr3t");
+
+        let source = SecretSource::Command("exit 1 # t0ken".to_string());
+        assert!(!format!("{:?}", source).contains("t0ken"));
+        let err = source.resolve().err().unwrap();
+        assert_eq!(err.kind(), ErrorKind::InvalidConfig);
+        assert!(!err.to_string().contains("t0ken"));
+    }
+}
@@ -0,0 +1,162 @@
+// Copyright 2019 Dmitry Tantsur <divius.inside@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Service-specific options.
+
+use reqwest::{IntoUrl, Url};
+
+use super::{ApiVersion, Error};
+
+/// Options applied to a single service of a [Session](struct.Session.html).
+///
+/// These options take precedence over the ones of the `Session` and its authentication type:
+///
+/// ```rust,no_run
+/// let options = osauth::ServiceOptions::new()
+///     .with_endpoint_override("http://127.0.0.1:6385")
+///     .expect("Invalid endpoint URL")
+///     .with_default_api_version(Some(osauth::ApiVersion(1, 58)));
+///
+/// let session = osauth::from_env()
+///     .expect("Failed to create an identity provider from the environment")
+///     .with_service_options("baremetal", options);
+/// let adapter = session.into_adapter(osauth::services::BAREMETAL);
+/// assert_eq!(adapter.default_api_version(), Some(osauth::ApiVersion(1, 58)));
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ServiceOptions {
+    endpoint_override: Option<Url>,
+    fallback_endpoints: Vec<Url>,
+    interface: Option<String>,
+    region_name: Option<String>,
+    default_api_version: Option<ApiVersion>,
+}
+
+impl ServiceOptions {
+    /// Create empty options.
+    #[inline]
+    pub fn new() -> ServiceOptions {
+        ServiceOptions::default()
+    }
+
+    /// Endpoint to use instead of the one from the service catalog (if any).
+    #[inline]
+    pub fn endpoint_override(&self) -> Option<&Url> {
+        self.endpoint_override.as_ref()
+    }
+
+    /// Endpoints to fail over to if the primary ones are unavailable.
+    #[inline]
+    pub fn fallback_endpoints(&self) -> &[Url] {
+        &self.fallback_endpoints
+    }
+
+    /// Endpoint interface to use for this service (if any).
+    #[inline]
+    pub fn interface(&self) -> Option<&str> {
+        self.interface.as_deref()
+    }
+
+    /// Region to use for this service (if any).
+    #[inline]
+    pub fn region_name(&self) -> Option<&str> {
+        self.region_name.as_deref()
+    }
+
+    /// Default API version for adapters of this service (if any).
+    #[inline]
+    pub fn default_api_version(&self) -> Option<ApiVersion> {
+        self.default_api_version
+    }
+
+    /// Whether no options are set.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        *self == ServiceOptions::default()
+    }
+
+    /// Set an endpoint to use instead of the one from the service catalog.
+    #[inline]
+    pub fn set_endpoint_override<U: IntoUrl>(&mut self, url: U) -> Result<(), Error> {
+        self.endpoint_override = Some(url.into_url()?);
+        Ok(())
+    }
+
+    /// Add an endpoint to fail over to if the primary ones are unavailable.
+    ///
+    /// Fallback endpoints are tried in the order they were added, after the endpoint override or
+    /// the endpoints from the service catalog.
+    #[inline]
+    pub fn add_fallback_endpoint<U: IntoUrl>(&mut self, url: U) -> Result<(), Error> {
+        self.fallback_endpoints.push(url.into_url()?);
+        Ok(())
+    }
+
+    /// Set an endpoint interface to use for this service.
+    ///
+    /// An interface set explicitly for the session or the adapter takes precedence.
+    #[inline]
+    pub fn set_interface<S: Into<String>>(&mut self, interface: S) {
+        self.interface = Some(interface.into());
+    }
+
+    /// Set a region to use for this service.
+    ///
+    /// A region set explicitly for the session or the adapter takes precedence.
+    #[inline]
+    pub fn set_region_name<S: Into<String>>(&mut self, region_name: S) {
+        self.region_name = Some(region_name.into());
+    }
+
+    /// Set the default API version for adapters of this service.
+    #[inline]
+    pub fn set_default_api_version(&mut self, api_version: Option<ApiVersion>) {
+        self.default_api_version = api_version;
+    }
+
+    /// Set an endpoint to use instead of the one from the service catalog.
+    #[inline]
+    pub fn with_endpoint_override<U: IntoUrl>(mut self, url: U) -> Result<Self, Error> {
+        self.set_endpoint_override(url)?;
+        Ok(self)
+    }
+
+    /// Add an endpoint to fail over to if the primary ones are unavailable.
+    #[inline]
+    pub fn with_fallback_endpoint<U: IntoUrl>(mut self, url: U) -> Result<Self, Error> {
+        self.add_fallback_endpoint(url)?;
+        Ok(self)
+    }
+
+    /// Set an endpoint interface to use for this service.
+    #[inline]
+    pub fn with_interface<S: Into<String>>(mut self, interface: S) -> Self {
+        self.set_interface(interface);
+        self
+    }
+
+    /// Set a region to use for this service.
+    #[inline]
+    pub fn with_region_name<S: Into<String>>(mut self, region_name: S) -> Self {
+        self.set_region_name(region_name);
+        self
+    }
+
+    /// Set the default API version for adapters of this service.
+    #[inline]
+    pub fn with_default_api_version(mut self, api_version: Option<ApiVersion>) -> Self {
+        self.set_default_api_version(api_version);
+        self
+    }
+}
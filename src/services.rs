@@ -21,6 +21,9 @@ use super::{ApiVersion, Error, ErrorKind};
 /// Trait representing a service type.
 pub trait ServiceType {
     /// Service type to pass to the catalog.
+    ///
+    /// Use the official type, its historical aliases are tried automatically, see
+    /// [ServiceTypes](../catalog/struct.ServiceTypes.html).
     fn catalog_type(&self) -> &'static str;
 
     /// Check whether this service type is compatible with the given major version.
@@ -105,6 +108,34 @@ macro_rules! service {
         }
     };
 
+    ($(#[$attr:meta])* $var:ident: $cls:ident -> $name:expr, major $ver:expr) => {
+        $(#[$attr])*
+        #[derive(Copy, Clone, Debug)]
+        pub struct $cls {
+            __use_new: (),
+        }
+
+        impl $cls {
+            /// Create a new service type.
+            pub const fn new() -> $cls {
+                $cls { __use_new: () }
+            }
+        }
+
+        impl $crate::services::ServiceType for $cls {
+            fn catalog_type(&self) -> &'static str {
+                $name
+            }
+
+            fn major_version_supported(&self, version: ApiVersion) -> bool {
+                version.0 == $ver
+            }
+        }
+
+        $(#[$attr])*
+        pub const $var: $cls = $cls::new();
+    };
+
     ($(#[$attr:meta])* $var:ident: $cls:ident -> $name:expr, header $hdr:expr) => {
         $(#[$attr])*
         #[derive(Copy, Clone, Debug)]
@@ -174,7 +205,10 @@ service! {
 
 service! {
     #[doc = "Block Storage service (v3)."]
-    BLOCK_STORAGE: BlockStorageService -> "volumev3"
+    #[doc = ""]
+    #[doc = "Catalogs using the `volumev3`, `volumev2` or `volume` service type are also supported,"]
+    #[doc = "but only major version 3 of the API is used."]
+    BLOCK_STORAGE: BlockStorageService -> "block-storage", major 3
 }
 
 impl GenericService {
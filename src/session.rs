@@ -14,25 +14,68 @@
 
 //! Session structure definition.
 
+use std::collections::HashMap;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
-use futures::future;
+use futures::future::{self, Loop};
 use futures::prelude::*;
 use log::{debug, trace};
 use reqwest::header::HeaderMap;
-use reqwest::r#async::{RequestBuilder, Response};
+use reqwest::r#async::{Client, RequestBuilder, Response};
 use reqwest::{Method, Url};
 use serde::de::DeserializeOwned;
 use serde::Serialize;
 
 use super::cache;
+use super::catalog::{self, Catalog};
+use super::health::{self, EndpointHealth, DEFAULT_COOLDOWN};
 use super::protocol::ServiceInfo;
 use super::request;
+use super::rewrite::RewriteRules;
 use super::services::ServiceType;
 use super::url;
-use super::{Adapter, ApiVersion, AuthType, Error};
+use super::{
+    Adapter, ApiVersion, AuthType, EndpointFilter, Error, ErrorKind, ProxyConfig, ServiceOptions,
+    TlsConfig,
+};
 
-type Cache = cache::MapCache<&'static str, ServiceInfo>;
+/// Key of the cached service information.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+struct CacheKey {
+    service_type: &'static str,
+    interface: Option<String>,
+    region: Option<String>,
+}
+
+/// Cached service information with the endpoint it was fetched from.
+#[derive(Debug)]
+struct CachedInfo {
+    endpoint: Url,
+    info: ServiceInfo,
+    has_alternatives: bool,
+    fetched_at: Instant,
+}
+
+impl CachedInfo {
+    #[inline]
+    fn new(endpoint: Url, info: ServiceInfo, has_alternatives: bool) -> CachedInfo {
+        CachedInfo {
+            endpoint,
+            info,
+            has_alternatives,
+            fetched_at: Instant::now(),
+        }
+    }
+
+    #[inline]
+    fn is_expired(&self, ttl: Option<Duration>) -> bool {
+        ttl.map(|ttl| self.fetched_at.elapsed() >= ttl)
+            .unwrap_or(false)
+    }
+}
+
+type Cache = cache::MapCache<CacheKey, CachedInfo>;
 
 /// An OpenStack API session.
 ///
@@ -43,11 +86,35 @@ type Cache = cache::MapCache<&'static str, ServiceInfo>;
 ///
 /// All clones of one session share the same authentication and endpoint cache. Use
 /// [with_auth_type](#method.with_auth_type) to detach a session.
+///
+/// Service information is cached per service type, endpoint interface and region, so clones
+/// using different interfaces or regions do not interfere. Cached information can be dropped
+/// using [invalidate_service_info](#method.invalidate_service_info) or expire after
+/// [a time-to-live](#method.set_service_info_ttl).
+///
+/// # Failover
+///
+/// If several endpoints match a service (e.g. the service catalog lists several of them or
+/// [fallback endpoints](struct.ServiceOptions.html#method.add_fallback_endpoint) are configured),
+/// an endpoint that returns a connection error, HTTP 502 or HTTP 503 is marked as failed. The
+/// next request uses the next endpoint, a failed endpoint is not tried again until the
+/// [failover cool-down](#method.set_failover_cooldown) passes. Requests with idempotent methods
+/// (e.g. GET, PUT or DELETE) are retried once with the next endpoint right away, but only if
+/// there is another endpoint to try. Clones of a session share the health of endpoints.
 #[derive(Debug, Clone)]
 pub struct Session {
     auth: Arc<AuthType>,
     cached_info: Arc<Cache>,
     endpoint_interface: Option<String>,
+    endpoint_filter: EndpointFilter,
+    region: Option<String>,
+    rewrite_rules: Arc<RewriteRules>,
+    health: Arc<EndpointHealth>,
+    failover_cooldown: Duration,
+    service_info_ttl: Option<Duration>,
+    service_options: HashMap<String, ServiceOptions>,
+    tls_config: TlsConfig,
+    proxy_config: ProxyConfig,
 }
 
 impl Session {
@@ -60,6 +127,15 @@ impl Session {
             auth: Arc::new(auth_type),
             cached_info: Arc::new(cache::MapCache::default()),
             endpoint_interface: None,
+            endpoint_filter: EndpointFilter::default(),
+            region: None,
+            rewrite_rules: Arc::new(RewriteRules::default()),
+            health: Arc::new(EndpointHealth::default()),
+            failover_cooldown: DEFAULT_COOLDOWN,
+            service_info_ttl: None,
+            service_options: HashMap::new(),
+            tls_config: TlsConfig::default(),
+            proxy_config: ProxyConfig::default(),
         }
     }
 
@@ -67,6 +143,7 @@ impl Session {
     ///
     /// The new `Adapter` will share the same authentication and will initially use the same
     /// endpoint interface (although it can be changed later without affecting the `Session`).
+    /// Its default API version comes from the [service options](#method.service_options).
     ///
     /// If you don't need the `Session` any more, using [into_adapter](#method.into_adapter) is a
     /// bit more efficient.
@@ -77,7 +154,7 @@ impl Session {
     /// let adapter = session.adapter(osauth::services::COMPUTE);
     /// ```
     #[inline]
-    pub fn adapter<Srv>(&self, service: Srv) -> Adapter<Srv> {
+    pub fn adapter<Srv: ServiceType>(&self, service: Srv) -> Adapter<Srv> {
         Adapter::from_session(self.clone(), service)
     }
 
@@ -95,7 +172,7 @@ impl Session {
     /// let adapter = session.into_adapter(osauth::services::COMPUTE);
     /// ```
     #[inline]
-    pub fn into_adapter<Srv>(self, service: Srv) -> Adapter<Srv> {
+    pub fn into_adapter<Srv: ServiceType>(self, service: Srv) -> Adapter<Srv> {
         Adapter::from_session(self, service)
     }
 
@@ -111,6 +188,66 @@ impl Session {
         &self.endpoint_interface
     }
 
+    /// Endpoint filter in use.
+    #[inline]
+    pub fn endpoint_filter(&self) -> &EndpointFilter {
+        &self.endpoint_filter
+    }
+
+    /// Region in use (if any).
+    ///
+    /// Returns the region of the authentication if no region is set for the session or in the
+    /// endpoint filter.
+    pub fn region(&self) -> Option<String> {
+        self.region
+            .clone()
+            .or_else(|| self.endpoint_filter.region().map(ToString::to_string))
+            .or_else(|| self.auth.region())
+    }
+
+    /// TLS options of the HTTP client.
+    #[inline]
+    pub fn tls_config(&self) -> &TlsConfig {
+        &self.tls_config
+    }
+
+    /// Proxy options of the HTTP client.
+    #[inline]
+    pub fn proxy_config(&self) -> &ProxyConfig {
+        &self.proxy_config
+    }
+
+    /// Rules used to rewrite endpoint URLs.
+    #[inline]
+    pub fn rewrite_rules(&self) -> &RewriteRules {
+        &self.rewrite_rules
+    }
+
+    /// Time before a failed endpoint is tried again.
+    #[inline]
+    pub fn failover_cooldown(&self) -> Duration {
+        self.failover_cooldown
+    }
+
+    /// Time after which cached service information expires (if any).
+    #[inline]
+    pub fn service_info_ttl(&self) -> Option<Duration> {
+        self.service_info_ttl
+    }
+
+    /// Options for the given service type (if any).
+    ///
+    /// Aliases of service types are resolved, e.g. `volumev3` returns the options of
+    /// `block-storage`.
+    #[inline]
+    pub fn service_options(&self, service_type: &str) -> Option<&ServiceOptions> {
+        let service_types = catalog::service_types();
+        let official = service_types
+            .official_type(service_type)
+            .unwrap_or(service_type);
+        self.service_options.get(official)
+    }
+
     /// Update the authentication and purges cached endpoint information.
     ///
     /// # Warning
@@ -129,6 +266,17 @@ impl Session {
         self.cached_info = Arc::new(cache::MapCache::default());
     }
 
+    /// Drop the cached information about the service.
+    ///
+    /// The information is dropped for all endpoint interfaces and regions and is fetched again on
+    /// the next request. Clones of this `Session` are also affected, since they share the cache.
+    pub fn invalidate_service_info<Srv: ServiceType>(&self, service: Srv) {
+        let catalog_type = service.catalog_type();
+        debug!("Dropping cached information for service {}", catalog_type);
+        self.cached_info
+            .retain(|key, _| key.service_type != catalog_type);
+    }
+
     /// Set a new authentication for this `Session`.
     ///
     /// This call clears the cached service information for this `Session`.
@@ -139,18 +287,120 @@ impl Session {
         self.auth = Arc::new(auth_type);
     }
 
-    /// Set endpoint interface to use.
+    /// Use the given proxy options for authentication and service requests.
+    ///
+    /// The HTTP client of the authentication is replaced by a new one with these proxy options
+    /// and the [TLS options](#method.tls_config) of this session, e.g. the ones from
+    /// `clouds.yaml`.
+    ///
+    /// Fails with `InvalidInput` if the authentication type does not support changing its HTTP
+    /// client (see [AuthType::clone_with_client](trait.AuthType.html#method.clone_with_client)).
+    ///
+    /// This call clears the cached service information for this `Session`.
+    /// It does not, however, affect clones of this `Session`.
+    pub fn set_proxy(&mut self, proxy: ProxyConfig) -> Result<(), Error> {
+        let tls = self.tls_config.clone();
+        self.replace_client(tls, proxy)
+    }
+
+    /// Use the given TLS options for authentication and service requests.
+    ///
+    /// The HTTP client of the authentication is replaced by a new one with these TLS options
+    /// and the [proxy options](#method.proxy_config) of this session. Fails like
+    /// [set_proxy](#method.set_proxy).
     ///
     /// This call clears the cached service information for this `Session`.
     /// It does not, however, affect clones of this `Session`.
+    pub fn set_tls_config(&mut self, tls: TlsConfig) -> Result<(), Error> {
+        let proxy = self.proxy_config.clone();
+        self.replace_client(tls, proxy)
+    }
+
+    /// Set endpoint interface to use.
+    ///
+    /// The interface takes precedence over the interfaces of the
+    /// [service options](#method.set_service_options) and the
+    /// [endpoint filter](#method.set_endpoint_filter).
+    ///
+    /// Service information is cached per interface, so this call does not clear the cache.
     pub fn set_endpoint_interface<S>(&mut self, endpoint_interface: S)
     where
         S: Into<String>,
     {
-        self.reset_cache();
         self.endpoint_interface = Some(endpoint_interface.into());
     }
 
+    /// Set the filter used to select endpoints from the service catalog.
+    ///
+    /// The interfaces of the filter are only used if no endpoint interface is set for the session
+    /// or the service. Similarly, a region from the service options takes precedence.
+    ///
+    /// This call clears the cached service information for this `Session`.
+    /// It does not, however, affect clones of this `Session`.
+    pub fn set_endpoint_filter(&mut self, filter: EndpointFilter) {
+        self.reset_cache();
+        self.endpoint_filter = filter;
+    }
+
+    /// Set the region to use.
+    ///
+    /// The region takes precedence over the regions of the authentication, the
+    /// [endpoint filter](#method.set_endpoint_filter) and the
+    /// [service options](#method.set_service_options).
+    ///
+    /// Service information is cached per region, so this call does not clear the cache.
+    pub fn set_region<S>(&mut self, region: S)
+    where
+        S: Into<String>,
+    {
+        self.region = Some(region.into());
+    }
+
+    /// Set the rules used to rewrite endpoint URLs.
+    ///
+    /// This call clears the cached service information for this `Session`.
+    /// It does not, however, affect clones of this `Session`.
+    pub fn set_rewrite_rules(&mut self, rules: RewriteRules) {
+        self.reset_cache();
+        self.rewrite_rules = Arc::new(rules);
+    }
+
+    /// Set the time before a failed endpoint is tried again.
+    ///
+    /// The default is one minute.
+    #[inline]
+    pub fn set_failover_cooldown(&mut self, cooldown: Duration) {
+        self.failover_cooldown = cooldown;
+    }
+
+    /// Set the time after which cached service information expires.
+    ///
+    /// By default, cached information does not expire.
+    #[inline]
+    pub fn set_service_info_ttl(&mut self, ttl: Option<Duration>) {
+        self.service_info_ttl = ttl;
+    }
+
+    /// Set options for the given service type.
+    ///
+    /// Options for an alias of a service type apply to the official type, e.g. options for
+    /// `volumev3` are used for `block-storage`.
+    ///
+    /// This call clears the cached service information for this `Session`.
+    /// It does not, however, affect clones of this `Session`.
+    pub fn set_service_options<S>(&mut self, service_type: S, options: ServiceOptions)
+    where
+        S: Into<String>,
+    {
+        self.reset_cache();
+        let service_type = service_type.into();
+        let official = catalog::service_types()
+            .official_type(&service_type)
+            .map(ToString::to_string)
+            .unwrap_or(service_type);
+        let _ = self.service_options.insert(official, options);
+    }
+
     /// Convert this session into one using the given authentication.
     #[inline]
     pub fn with_auth_type<Auth: AuthType + 'static>(mut self, auth_method: Auth) -> Session {
@@ -158,6 +408,20 @@ impl Session {
         self
     }
 
+    /// Convert this session into one using the given proxy options.
+    #[inline]
+    pub fn with_proxy(mut self, proxy: ProxyConfig) -> Result<Session, Error> {
+        self.set_proxy(proxy)?;
+        Ok(self)
+    }
+
+    /// Convert this session into one using the given TLS options.
+    #[inline]
+    pub fn with_tls_config(mut self, tls: TlsConfig) -> Result<Session, Error> {
+        self.set_tls_config(tls)?;
+        Ok(self)
+    }
+
     /// Convert this session into one using the given endpoint interface.
     #[inline]
     pub fn with_endpoint_interface<S>(mut self, endpoint_interface: S) -> Session
@@ -168,6 +432,151 @@ impl Session {
         self
     }
 
+    /// Convert this session into one using the given endpoint filter.
+    #[inline]
+    pub fn with_endpoint_filter(mut self, filter: EndpointFilter) -> Session {
+        self.set_endpoint_filter(filter);
+        self
+    }
+
+    /// Convert this session into one using the given region.
+    #[inline]
+    pub fn with_region<S>(mut self, region: S) -> Session
+    where
+        S: Into<String>,
+    {
+        self.set_region(region);
+        self
+    }
+
+    /// Create a copy of this session using the given region.
+    ///
+    /// The copy shares the authentication and the cached information with this session, which
+    /// makes it cheap to use for a single request:
+    ///
+    /// ```rust,no_run
+    /// use futures::Future;
+    ///
+    /// let session =
+    ///     osauth::from_env().expect("Failed to create an identity provider from the environment");
+    /// let future = session
+    ///     .in_region("RegionTwo")
+    ///     .get(osauth::services::COMPUTE, &["servers"], None);
+    /// ```
+    #[inline]
+    pub fn in_region<S>(&self, region: S) -> Session
+    where
+        S: Into<String>,
+    {
+        self.clone().with_region(region)
+    }
+
+    /// Convert this session into one using the given rules to rewrite endpoint URLs.
+    #[inline]
+    pub fn with_rewrite_rules(mut self, rules: RewriteRules) -> Session {
+        self.set_rewrite_rules(rules);
+        self
+    }
+
+    /// Convert this session into one using the given failover cool-down.
+    #[inline]
+    pub fn with_failover_cooldown(mut self, cooldown: Duration) -> Session {
+        self.set_failover_cooldown(cooldown);
+        self
+    }
+
+    /// Convert this session into one using the given time-to-live of cached service information.
+    #[inline]
+    pub fn with_service_info_ttl(mut self, ttl: Option<Duration>) -> Session {
+        self.set_service_info_ttl(ttl);
+        self
+    }
+
+    /// Convert this session into one using the given options for the service type.
+    #[inline]
+    pub fn with_service_options<S>(mut self, service_type: S, options: ServiceOptions) -> Session
+    where
+        S: Into<String>,
+    {
+        self.set_service_options(service_type, options);
+        self
+    }
+
+    /// Get the service catalog.
+    ///
+    /// The catalog is empty for authentication types without one.
+    ///
+    /// ```rust,no_run
+    /// use futures::Future;
+    ///
+    /// let session =
+    ///     osauth::from_env().expect("Failed to create an identity provider from the environment");
+    /// let future = session.catalog().map(|catalog| {
+    ///     println!("Available services: {:?}", catalog.service_types());
+    ///     println!("Available regions: {:?}", catalog.regions());
+    /// });
+    /// ```
+    #[inline]
+    pub fn catalog(&self) -> impl Future<Item = Catalog, Error = Error> + Send {
+        self.auth.get_catalog()
+    }
+
+    /// Run the same call in every region of the service.
+    ///
+    /// The regions are taken from the service catalog, the call receives a copy of this session
+    /// for each of them (see [in_region](#method.in_region)). Calls run concurrently, their
+    /// results are returned per region, sorted by the region name. The result is empty for
+    /// authentication types without a service catalog.
+    ///
+    /// ```rust,no_run
+    /// use futures::Future;
+    /// use osauth::services::COMPUTE;
+    ///
+    /// let session =
+    ///     osauth::from_env().expect("Failed to create an identity provider from the environment");
+    /// let future = session
+    ///     .for_each_region(COMPUTE, |session| {
+    ///         session.get_json::<_, _, serde_json::Value>(COMPUTE, &["os-hypervisors"], None)
+    ///     })
+    ///     .map(|results| {
+    ///         for (region, result) in results {
+    ///             println!("{}: {:?}", region, result);
+    ///         }
+    ///     });
+    /// ```
+    pub fn for_each_region<Srv, F, R>(
+        &self,
+        service: Srv,
+        func: F,
+    ) -> impl Future<Item = Vec<(String, Result<R::Item, Error>)>, Error = Error> + Send
+    where
+        Srv: ServiceType,
+        F: Fn(Session) -> R + Send,
+        R: IntoFuture<Error = Error>,
+        R::Future: Send,
+        R::Item: Send,
+    {
+        let catalog_type = service.catalog_type();
+        let session = self.clone();
+        self.catalog().and_then(move |catalog| {
+            let regions = catalog.regions_of(catalog_type);
+            debug!(
+                "Running a call for {} in regions {:?}",
+                catalog_type, regions
+            );
+            let calls = regions
+                .into_iter()
+                .map(|region| {
+                    let region = region.to_string();
+                    func(session.in_region(region.clone()))
+                        .into_future()
+                        .then(move |result| Ok((region, result)))
+                })
+                .collect::<Vec<_>>();
+            future::join_all(calls)
+        })
+    }
+
     /// Get minimum/maximum API (micro)version information.
     ///
     /// Returns `None` if the range cannot be determined, which usually means
@@ -326,27 +735,8 @@ impl Session {
         I::Item: AsRef<str>,
         I::IntoIter: Send,
     {
-        let auth = Arc::clone(&self.auth);
-        self.get_endpoint(service.clone(), path)
-            .and_then(move |url| {
-                trace!(
-                    "Sending HTTP {} request to {} with API version {:?}",
-                    method,
-                    url,
-                    api_version
-                );
-                auth.request(method, url)
-            })
-            .and_then(move |mut builder| {
-                if let Some(version) = api_version {
-                    let mut headers = HeaderMap::new();
-                    match service.set_api_version_headers(&mut headers, version) {
-                        Ok(()) => builder = builder.headers(headers),
-                        Err(err) => return future::err(err),
-                    }
-                }
-                future::ok(builder)
-            })
+        self.request_with_endpoint(service, method, path, api_version)
+            .map(|(_, builder)| builder)
     }
 
     /// Start a GET request.
@@ -393,8 +783,7 @@ impl Session {
         I::Item: AsRef<str>,
         I::IntoIter: Send,
     {
-        self.request(service, Method::GET, path, api_version)
-            .then(request::send_checked)
+        self.send_checked(service, Method::GET, path, api_version, |builder| builder)
     }
 
     /// Fetch a JSON using the GET request.
@@ -436,8 +825,7 @@ impl Session {
         I::IntoIter: Send,
         T: DeserializeOwned + Send,
     {
-        self.request(service, Method::GET, path, api_version)
-            .then(request::fetch_json)
+        self.fetch_json(service, Method::GET, path, api_version, |builder| builder)
     }
 
     /// Fetch a JSON using the GET request with a query.
@@ -460,9 +848,9 @@ impl Session {
         Q: Serialize + Send,
         T: DeserializeOwned + Send,
     {
-        self.request(service, Method::GET, path, api_version)
-            .map(move |builder| builder.query(&query))
-            .then(request::fetch_json)
+        self.fetch_json(service, Method::GET, path, api_version, move |builder| {
+            builder.query(&query)
+        })
     }
 
     /// Issue a GET request with a query
@@ -484,9 +872,9 @@ impl Session {
         I::IntoIter: Send,
         Q: Serialize + Send,
     {
-        self.request(service, Method::GET, path, api_version)
-            .map(move |builder| builder.query(&query))
-            .then(request::send_checked)
+        self.send_checked(service, Method::GET, path, api_version, move |builder| {
+            builder.query(&query)
+        })
     }
 
     /// Start a POST request.
@@ -529,9 +917,9 @@ impl Session {
         I::IntoIter: Send,
         T: Serialize + Send,
     {
-        self.request(service, Method::POST, path, api_version)
-            .map(move |builder| builder.json(&body))
-            .then(request::send_checked)
+        self.send_checked(service, Method::POST, path, api_version, move |builder| {
+            builder.json(&body)
+        })
     }
 
     /// POST a JSON object and receive a JSON back.
@@ -555,9 +943,9 @@ impl Session {
         T: Serialize + Send,
         R: DeserializeOwned + Send,
     {
-        self.request(service, Method::POST, path, api_version)
-            .map(move |builder| builder.json(&body))
-            .then(request::fetch_json)
+        self.fetch_json(service, Method::POST, path, api_version, move |builder| {
+            builder.json(&body)
+        })
     }
 
     /// Start a PUT request.
@@ -600,9 +988,9 @@ impl Session {
         I::IntoIter: Send,
         T: Serialize + Send,
     {
-        self.request(service, Method::PUT, path, api_version)
-            .map(move |builder| builder.json(&body))
-            .then(request::send_checked)
+        self.send_checked(service, Method::PUT, path, api_version, move |builder| {
+            builder.json(&body)
+        })
     }
 
     /// Issue an empty PUT request.
@@ -621,8 +1009,7 @@ impl Session {
         I::Item: AsRef<str>,
         I::IntoIter: Send,
     {
-        self.request(service, Method::PUT, path, api_version)
-            .then(request::send_checked)
+        self.send_checked(service, Method::PUT, path, api_version, |builder| builder)
     }
 
     /// PUT a JSON object and receive a JSON back.
@@ -646,9 +1033,9 @@ impl Session {
         T: Serialize + Send,
         R: DeserializeOwned + Send,
     {
-        self.request(service, Method::PUT, path, api_version)
-            .map(move |builder| builder.json(&body))
-            .then(request::fetch_json)
+        self.fetch_json(service, Method::PUT, path, api_version, move |builder| {
+            builder.json(&body)
+        })
     }
 
     /// Start a DELETE request.
@@ -687,11 +1074,13 @@ impl Session {
         I::Item: AsRef<str>,
         I::IntoIter: Send,
     {
-        self.request(service, Method::DELETE, path, api_version)
-            .then(request::send_checked)
+        self.send_checked(service, Method::DELETE, path, api_version, |builder| {
+            builder
+        })
     }
 
     /// Ensure service info and return the cache.
+    #[inline]
     fn extract_service_info<Srv, F, T>(
         &self,
         service: Srv,
@@ -701,54 +1090,310 @@ impl Session {
         Srv: ServiceType + Send,
         F: FnOnce(&ServiceInfo) -> T + Send,
         T: Send,
+    {
+        self.service_info(service)
+            .map(move |(_, info)| filter(&info))
+    }
+
+    /// Ensure service info and return it with the endpoint it was fetched from.
+    fn service_info<Srv>(
+        &self,
+        service: Srv,
+    ) -> impl Future<Item = (Url, ServiceInfo), Error = Error>
+    where
+        Srv: ServiceType + Send,
     {
         let catalog_type = service.catalog_type();
-        if self.cached_info.is_set(&catalog_type) {
-            future::Either::A(future::ok(
-                self.cached_info
-                    .extract(&catalog_type, filter)
-                    .expect("BUG: cached record removed while in extract_service_info"),
-            ))
+        let key = self.cache_key(catalog_type);
+        let ttl = self.service_info_ttl;
+        let cached = self
+            .cached_info
+            .extract(&key, |cached| {
+                if cached.is_expired(ttl) {
+                    None
+                } else {
+                    Some((cached.endpoint.clone(), cached.info.clone()))
+                }
+            })
+            .flatten();
+        if let Some(cached) = cached {
+            future::Either::A(future::ok(cached))
         } else {
             debug!(
-                "No cached information for service {}, fetching",
-                catalog_type
+                "No cached information for service {} with interface {:?} in region {:?}, fetching",
+                catalog_type, key.interface, key.region
             );
 
-            let endpoint_interface = self.endpoint_interface.clone();
+            let options = self.service_options(catalog_type);
+            let fallback = options
+                .map(|opts| opts.fallback_endpoints().to_vec())
+                .unwrap_or_default();
+            let has_fallback = !fallback.is_empty();
+            let endpoints = match options.and_then(ServiceOptions::endpoint_override) {
+                Some(url) => {
+                    debug!(
+                        "Using endpoint override {} for service {}",
+                        url, catalog_type
+                    );
+                    future::Either::A(future::ok(vec![url.clone()]))
+                }
+                None => {
+                    let mut filter = self.endpoint_filter.clone();
+                    if let Some(ref endpoint_interface) = key.interface {
+                        filter.set_interfaces(vec![endpoint_interface.clone()]);
+                    }
+                    if let Some(ref region) = key.region {
+                        filter.set_region(region.clone());
+                    }
+                    future::Either::B(self.auth.find_endpoints(catalog_type.to_string(), filter))
+                }
+            };
+
             let cached_info = Arc::clone(&self.cached_info);
             let auth_type = Arc::clone(&self.auth);
+            let rules = Arc::clone(&self.rewrite_rules);
+            let health = Arc::clone(&self.health);
+            let cooldown = self.failover_cooldown;
             future::Either::B(
-                self.auth
-                    .get_endpoint(catalog_type.to_string(), endpoint_interface)
-                    .and_then(move |ep| ServiceInfo::fetch(service, ep, auth_type))
-                    .map(move |info| {
-                        let value = filter(&info);
-                        cached_info.set(catalog_type, info);
-                        value
+                endpoints
+                    .or_else(move |e| {
+                        // The fallback endpoints may still be usable.
+                        if e.kind() == ErrorKind::EndpointNotFound && has_fallback {
+                            Ok(Vec::new())
+                        } else {
+                            Err(e)
+                        }
+                    })
+                    .map(move |mut endpoints| {
+                        for url in fallback {
+                            if !endpoints.contains(&url) {
+                                endpoints.push(url);
+                            }
+                        }
+                        endpoints
+                    })
+                    .and_then(move |endpoints| {
+                        let has_alternatives = endpoints.len() > 1;
+                        ServiceInfo::fetch(service, endpoints, auth_type, rules, health, cooldown)
+                            .map(move |(endpoint, info)| (endpoint, info, has_alternatives))
+                    })
+                    .map(move |(endpoint, info, has_alternatives)| {
+                        let cached =
+                            CachedInfo::new(endpoint.clone(), info.clone(), has_alternatives);
+                        cached_info.set(key, cached);
+                        (endpoint, info)
                     }),
             )
         }
     }
 
+    /// Build a request and return it with the endpoint it is sent to.
+    fn request_with_endpoint<Srv, I>(
+        &self,
+        service: Srv,
+        method: Method,
+        path: I,
+        api_version: Option<ApiVersion>,
+    ) -> impl Future<Item = (Url, RequestBuilder), Error = Error> + Send
+    where
+        Srv: ServiceType + Send + Clone,
+        I: IntoIterator,
+        I::Item: AsRef<str>,
+        I::IntoIter: Send,
+    {
+        let auth = Arc::clone(&self.auth);
+        let path_iter = path.into_iter();
+        self.service_info(service.clone())
+            .and_then(move |(endpoint, info)| {
+                let url = url::extend(info.root_url, path_iter);
+                trace!(
+                    "Sending HTTP {} request to {} with API version {:?}",
+                    method,
+                    url,
+                    api_version
+                );
+                auth.request(method, url)
+                    .map(move |builder| (endpoint, builder))
+            })
+            .and_then(move |(endpoint, mut builder)| {
+                if let Some(version) = api_version {
+                    let mut headers = HeaderMap::new();
+                    match service.set_api_version_headers(&mut headers, version) {
+                        Ok(()) => builder = builder.headers(headers),
+                        Err(err) => return future::err(err),
+                    }
+                }
+                future::ok((endpoint, builder))
+            })
+    }
+
+    /// Send the request and check its result.
+    ///
+    /// The `prepare` callback adds e.g. a body to the request. If the endpoint is unavailable, it
+    /// is marked as failed and the cached information is dropped (unless it already refers to
+    /// another endpoint), so that the next request fails over to another endpoint. Requests with
+    /// idempotent methods are retried once right away if another endpoint can be used.
+    pub(crate) fn send_checked<Srv, I, F>(
+        &self,
+        service: Srv,
+        method: Method,
+        path: I,
+        api_version: Option<ApiVersion>,
+        prepare: F,
+    ) -> impl Future<Item = Response, Error = Error> + Send
+    where
+        Srv: ServiceType + Send + Clone,
+        I: IntoIterator,
+        I::Item: AsRef<str>,
+        F: Fn(RequestBuilder) -> RequestBuilder + Send,
+    {
+        let path = path
+            .into_iter()
+            .map(|item| item.as_ref().to_string())
+            .collect::<Vec<_>>();
+        let key = self.cache_key(service.catalog_type());
+        let retry = method.is_idempotent();
+        let session = self.clone();
+        future::loop_fn((prepare, retry), move |(prepare, retry)| {
+            let key = key.clone();
+            let session_copy = session.clone();
+            session
+                .request_with_endpoint(service.clone(), method.clone(), path.clone(), api_version)
+                .and_then(move |(endpoint, builder)| {
+                    prepare(builder)
+                        .send()
+                        .then(move |result| Ok((endpoint, result, prepare)))
+                })
+                .map(move |(endpoint, result, prepare)| {
+                    if health::is_unavailable(&result) {
+                        let has_alternatives = session_copy.mark_failed(&key, &endpoint);
+                        if retry && has_alternatives {
+                            debug!("Retrying the request after a failure of {}", endpoint);
+                            return Loop::Continue((prepare, false));
+                        }
+                    }
+                    Loop::Break(result)
+                })
+        })
+        .and_then(request::check)
+    }
+
+    /// Send the request and parse the JSON response.
+    #[inline]
+    pub(crate) fn fetch_json<Srv, I, F, T>(
+        &self,
+        service: Srv,
+        method: Method,
+        path: I,
+        api_version: Option<ApiVersion>,
+        prepare: F,
+    ) -> impl Future<Item = T, Error = Error> + Send
+    where
+        Srv: ServiceType + Send + Clone,
+        I: IntoIterator,
+        I::Item: AsRef<str>,
+        F: Fn(RequestBuilder) -> RequestBuilder + Send,
+        T: DeserializeOwned + Send,
+    {
+        self.send_checked(service, method, path, api_version, prepare)
+            .and_then(|mut resp| resp.json().from_err())
+    }
+
+    /// Mark the endpoint as failed and drop the cached information if it refers to it.
+    ///
+    /// Returns whether another endpoint can be used for the service.
+    fn mark_failed(&self, key: &CacheKey, endpoint: &Url) -> bool {
+        self.health.mark_failed(endpoint);
+        match self
+            .cached_info
+            .remove_if(key, |cached| cached.endpoint == *endpoint)
+        {
+            Some(cached) => {
+                debug!("Dropped cached information for {:?}", key);
+                cached.has_alternatives
+            }
+            None => self
+                .cached_info
+                .extract(key, |cached| cached.endpoint != *endpoint)
+                .unwrap_or(false),
+        }
+    }
+
+    /// Remember the options the HTTP client of the authentication was created with.
+    pub(crate) fn set_client_options(&mut self, tls: TlsConfig, proxy: ProxyConfig) {
+        self.tls_config = tls;
+        self.proxy_config = proxy;
+    }
+
+    /// Replace the HTTP client of the authentication with one using the given options.
+    fn replace_client(&mut self, tls: TlsConfig, proxy: ProxyConfig) -> Result<(), Error> {
+        let client = tls.apply(proxy.apply(Client::builder()))?.build()?;
+        let auth = self.auth.clone_with_client(client).ok_or_else(|| {
+            Error::new(
+                ErrorKind::InvalidInput,
+                "The authentication type does not support changing its HTTP client",
+            )
+        })?;
+        self.reset_cache();
+        self.auth = Arc::from(auth);
+        self.set_client_options(tls, proxy);
+        Ok(())
+    }
+
+    /// Cache key for the service type, including the requested interface and region.
+    fn cache_key(&self, catalog_type: &'static str) -> CacheKey {
+        let options = self.service_options(catalog_type);
+        let interface = self.endpoint_interface.clone().or_else(|| {
+            options
+                .and_then(ServiceOptions::interface)
+                .map(ToString::to_string)
+        });
+        let region = self
+            .region
+            .clone()
+            .or_else(|| {
+                options
+                    .and_then(ServiceOptions::region_name)
+                    .map(ToString::to_string)
+            })
+            .or_else(|| self.endpoint_filter.region().map(ToString::to_string));
+        CacheKey {
+            service_type: catalog_type,
+            interface,
+            region,
+        }
+    }
+
     #[cfg(test)]
     pub(crate) fn cache_fake_service(
         &mut self,
         service_type: &'static str,
         service_info: ServiceInfo,
     ) {
-        let _ = self.cached_info.set(service_type, service_info);
+        let endpoint = service_info.root_url.clone();
+        let key = self.cache_key(service_type);
+        self.cached_info
+            .set(key, CachedInfo::new(endpoint, service_info, false));
     }
 }
 
 #[cfg(test)]
 pub(crate) mod test {
+    use std::io::{Read, Write};
+    use std::net::{TcpListener, TcpStream};
+    use std::thread;
+    use std::time::Duration;
+
     use futures::Future;
     use reqwest::Url;
+    use tokio::runtime::Runtime;
 
+    use super::super::catalog::Catalog;
     use super::super::protocol::ServiceInfo;
-    use super::super::services::{GenericService, VersionSelector};
-    use super::super::{ApiVersion, NoAuth};
+    use super::super::services::{GenericService, VersionSelector, BAREMETAL, OBJECT_STORAGE};
+    use super::super::{
+        ApiVersion, ErrorKind, NoAuth, ProxyConfig, ServiceOptions, StaticCatalog, TlsConfig,
+    };
     use super::Session;
 
     pub const URL: &str = "http://127.0.0.1:5000/";
@@ -774,6 +1419,14 @@ pub(crate) mod test {
 
     pub const FAKE: GenericService = GenericService::new("fake", VersionSelector::Any);
 
+    #[test]
+    fn test_catalog_without_auth() {
+        let s = new_simple_session(URL);
+        let cat = s.catalog().wait().unwrap();
+        assert!(cat.is_empty());
+        assert!(cat.service_types().is_empty());
+    }
+
     #[test]
     fn test_get_endpoint() {
         let s = new_simple_session(URL);
@@ -805,6 +1458,375 @@ pub(crate) mod test {
         assert!(res.is_none());
     }
 
+    #[test]
+    fn test_get_endpoint_override() {
+        let options = ServiceOptions::new()
+            .with_endpoint_override("http://127.0.0.1:8080/v1/")
+            .unwrap();
+        let s = Session::new(NoAuth::new(URL).unwrap());
+        let ep = s.get_endpoint(OBJECT_STORAGE, &["c"]).wait().unwrap();
+        assert_eq!(ep.as_str(), "http://127.0.0.1:5000/c");
+
+        let s = s.with_service_options("object-store", options);
+        let ep = s.get_endpoint(OBJECT_STORAGE, &["c"]).wait().unwrap();
+        assert_eq!(ep.as_str(), "http://127.0.0.1:8080/v1/c");
+    }
+
+    #[test]
+    fn test_failover_to_fallback() {
+        let options = ServiceOptions::new()
+            .with_fallback_endpoint("http://127.0.0.1:8080/v1/")
+            .unwrap();
+        let s = Session::new(NoAuth::new("http://127.0.0.1:1/v1/").unwrap())
+            .with_service_options("object-store", options);
+        let ep = s.get_endpoint(OBJECT_STORAGE, &["c"]).wait().unwrap();
+        assert_eq!(ep.as_str(), "http://127.0.0.1:1/v1/c");
+
+        // POST is not retried, the next request uses the fallback endpoint.
+        let mut rt = Runtime::new().unwrap();
+        let err = rt
+            .block_on(s.post(OBJECT_STORAGE, &["c"], "body", None))
+            .err()
+            .unwrap();
+        assert_eq!(err.kind(), ErrorKind::ProtocolError);
+
+        let ep = s.get_endpoint(OBJECT_STORAGE, &["c"]).wait().unwrap();
+        assert_eq!(ep.as_str(), "http://127.0.0.1:8080/v1/c");
+
+        // The failed endpoint is used again after the cool-down.
+        let mut s = s.with_failover_cooldown(Duration::from_secs(0));
+        s.refresh().wait().unwrap();
+        let ep = s.get_endpoint(OBJECT_STORAGE, &["c"]).wait().unwrap();
+        assert_eq!(ep.as_str(), "http://127.0.0.1:1/v1/c");
+    }
+
+    #[test]
+    fn test_retry_idempotent() {
+        let first = Url::parse("http://127.0.0.1:1/v1/").unwrap();
+        let second = Url::parse("http://127.0.0.1:2/v1/").unwrap();
+        let options = ServiceOptions::new()
+            .with_fallback_endpoint(second.as_str())
+            .unwrap();
+        let cooldown = Duration::from_secs(60);
+        let mut rt = Runtime::new().unwrap();
+
+        let s = Session::new(NoAuth::new(first.as_str()).unwrap())
+            .with_service_options("object-store", options.clone());
+        let err = rt
+            .block_on(s.post(OBJECT_STORAGE, &["c"], "body", None))
+            .err()
+            .unwrap();
+        assert_eq!(err.kind(), ErrorKind::ProtocolError);
+        assert!(!s.health.is_available(&first, cooldown));
+        assert!(s.health.is_available(&second, cooldown));
+
+        let s = Session::new(NoAuth::new(first.as_str()).unwrap())
+            .with_service_options("object-store", options);
+        let err = rt
+            .block_on(s.get(OBJECT_STORAGE, &["c"], None))
+            .err()
+            .unwrap();
+        assert_eq!(err.kind(), ErrorKind::ProtocolError);
+        // The request was retried with the fallback endpoint.
+        assert!(!s.health.is_available(&first, cooldown));
+        assert!(!s.health.is_available(&second, cooldown));
+    }
+
+    #[test]
+    fn test_no_retry_single_endpoint() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let url = format!("http://{}/v1/", addr);
+        let server = thread::spawn(move || {
+            let mut requests = 0;
+            for stream in listener.incoming() {
+                let mut stream = stream.unwrap();
+                let mut buf = [0; 1024];
+                let size = stream.read(&mut buf).unwrap();
+                if buf[..size].starts_with(b"STOP") {
+                    break;
+                }
+                requests += 1;
+                stream
+                    .write_all(
+                        b"HTTP/1.1 503 Service Unavailable\r\n\
+                          Content-Length: 0\r\nConnection: close\r\n\r\n",
+                    )
+                    .unwrap();
+            }
+            requests
+        });
+
+        let s = Session::new(NoAuth::new(url.as_str()).unwrap());
+        let mut rt = Runtime::new().unwrap();
+        let err = rt
+            .block_on(s.get(OBJECT_STORAGE, &["c"], None))
+            .err()
+            .unwrap();
+        assert_eq!(err.kind(), ErrorKind::InternalServerError);
+
+        // Only one request has been sent, stop the server.
+        TcpStream::connect(addr)
+            .unwrap()
+            .write_all(b"STOP")
+            .unwrap();
+        assert_eq!(server.join().unwrap(), 1);
+    }
+
+    #[test]
+    fn test_stale_failure() {
+        let failed = Url::parse("http://127.0.0.1:1/v1/").unwrap();
+        let mut s = new_simple_session("http://127.0.0.1:2/v1/");
+        let key = s.cache_key("fake");
+        // The cached endpoint has already changed, it is not affected and can be retried.
+        assert!(s.mark_failed(&key, &failed));
+        let ep = s.get_endpoint(FAKE, &["c"]).wait().unwrap();
+        assert_eq!(ep.as_str(), "http://127.0.0.1:2/v1/c");
+        assert!(!s.health.is_available(&failed, Duration::from_secs(60)));
+
+        s.cache_fake_service("fake", fake_service_info());
+        let current = Url::parse(URL).unwrap();
+        assert!(!s.mark_failed(&key, &current));
+        assert!(s
+            .cached_info
+            .extract(&key, |cached| cached.endpoint.clone())
+            .is_none());
+    }
+
+    #[test]
+    fn test_proxy() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let proxy = format!("http://{}", listener.local_addr().unwrap());
+        let server = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut buf = [0; 1024];
+            let size = stream.read(&mut buf).unwrap();
+            stream
+                .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n")
+                .unwrap();
+            String::from_utf8_lossy(&buf[..size]).into_owned()
+        });
+
+        let s = Session::new(NoAuth::new("http://cloud.invalid/v1/").unwrap())
+            .with_proxy(ProxyConfig::new().with_http_proxy(proxy.as_str()).unwrap())
+            .unwrap();
+        let mut rt = Runtime::new().unwrap();
+        let resp = rt.block_on(s.get(OBJECT_STORAGE, &["c"], None)).unwrap();
+        assert!(resp.status().is_success());
+
+        let request = server.join().unwrap();
+        assert!(request.starts_with("GET http://cloud.invalid/v1/c HTTP/1.1"));
+    }
+
+    #[test]
+    fn test_tls_config() {
+        let s = Session::new(NoAuth::new("https://cloud.invalid/v1/").unwrap())
+            .with_tls_config(TlsConfig::new().with_verify(false))
+            .unwrap();
+        assert!(!s.tls_config().verify());
+
+        let s = s
+            .with_proxy(
+                ProxyConfig::new()
+                    .with_https_proxy("http://proxy.local:3128")
+                    .unwrap(),
+            )
+            .unwrap();
+        assert!(!s.tls_config().verify());
+        assert!(s.proxy_config().https_proxy().is_some());
+
+        let s = s.with_tls_config(TlsConfig::new()).unwrap();
+        assert!(s.tls_config().verify());
+        assert!(s.proxy_config().https_proxy().is_some());
+    }
+
+    fn regional_catalog() -> Catalog {
+        Catalog::from_yaml(
+            r#"
+- type: object-store
+  endpoints:
+    - interface: public
+      region: RegionOne
+      url: http://host.one:8080/v1
+    - interface: public
+      region: RegionTwo
+      url: http://host.two:8080/v1
+    - interface: internal
+      region: RegionOne
+      url: http://internal.one:8080/v1
+"#,
+        )
+        .unwrap()
+    }
+
+    fn new_regional_session() -> Session {
+        Session::new(StaticCatalog::new("token", regional_catalog()).with_region("RegionOne"))
+    }
+
+    #[test]
+    fn test_no_region_ambiguous() {
+        let s = Session::new(StaticCatalog::new("token", regional_catalog()));
+        assert!(s.region().is_none());
+        let err = s.get_endpoint(OBJECT_STORAGE, &["c"]).wait().err().unwrap();
+        assert_eq!(err.kind(), ErrorKind::TooManyItems);
+
+        let ep = s
+            .in_region("RegionTwo")
+            .get_endpoint(OBJECT_STORAGE, &["c"])
+            .wait()
+            .unwrap();
+        assert_eq!(ep.as_str(), "http://host.two:8080/v1/c");
+    }
+
+    #[test]
+    fn test_region_override() {
+        let s = new_regional_session();
+        assert_eq!(s.region(), Some(String::from("RegionOne")));
+        let ep = s.get_endpoint(OBJECT_STORAGE, &["c"]).wait().unwrap();
+        assert_eq!(ep.as_str(), "http://host.one:8080/v1/c");
+
+        let s2 = s.in_region("RegionTwo");
+        assert_eq!(s2.region(), Some(String::from("RegionTwo")));
+        let ep = s2.get_endpoint(OBJECT_STORAGE, &["c"]).wait().unwrap();
+        assert_eq!(ep.as_str(), "http://host.two:8080/v1/c");
+
+        // The cache is shared, but keyed by the region.
+        let ep = s.get_endpoint(OBJECT_STORAGE, &["c"]).wait().unwrap();
+        assert_eq!(ep.as_str(), "http://host.one:8080/v1/c");
+
+        // The session region takes precedence over the service options.
+        let options = ServiceOptions::new().with_region_name("RegionOne");
+        let s3 = s2.with_service_options("object-store", options);
+        let ep = s3.get_endpoint(OBJECT_STORAGE, &["c"]).wait().unwrap();
+        assert_eq!(ep.as_str(), "http://host.two:8080/v1/c");
+    }
+
+    #[test]
+    fn test_for_each_region() {
+        let s = new_regional_session();
+        let result = s
+            .for_each_region(OBJECT_STORAGE, |session| {
+                session
+                    .get_endpoint(OBJECT_STORAGE, &["c"])
+                    .map(|url| url.to_string())
+            })
+            .wait()
+            .unwrap()
+            .into_iter()
+            .map(|(region, res)| (region, res.unwrap()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            result,
+            vec![
+                (
+                    String::from("RegionOne"),
+                    String::from("http://host.one:8080/v1/c")
+                ),
+                (
+                    String::from("RegionTwo"),
+                    String::from("http://host.two:8080/v1/c")
+                ),
+            ]
+        );
+
+        let s = new_simple_session(URL);
+        let result = s
+            .for_each_region(OBJECT_STORAGE, |session| {
+                session.get_endpoint(OBJECT_STORAGE, &["c"])
+            })
+            .wait()
+            .unwrap();
+        assert!(result.is_empty());
+    }
+
+    #[test]
+    fn test_interface_cache_key() {
+        let s = new_regional_session();
+        let ep = s.get_endpoint(OBJECT_STORAGE, &["c"]).wait().unwrap();
+        assert_eq!(ep.as_str(), "http://host.one:8080/v1/c");
+
+        let adapter = s
+            .adapter(OBJECT_STORAGE)
+            .with_endpoint_interface("internal");
+        let ep = adapter.get_endpoint(&["c"]).wait().unwrap();
+        assert_eq!(ep.as_str(), "http://internal.one:8080/v1/c");
+
+        // The cache is shared, but keyed by the interface.
+        let ep = s.get_endpoint(OBJECT_STORAGE, &["c"]).wait().unwrap();
+        assert_eq!(ep.as_str(), "http://host.one:8080/v1/c");
+    }
+
+    #[test]
+    fn test_interface_precedence() {
+        let options = ServiceOptions::new().with_interface("internal");
+        let s = new_regional_session().with_service_options("object-store", options);
+        let ep = s.get_endpoint(OBJECT_STORAGE, &["c"]).wait().unwrap();
+        assert_eq!(ep.as_str(), "http://internal.one:8080/v1/c");
+
+        let adapter = s.adapter(OBJECT_STORAGE).with_endpoint_interface("public");
+        let ep = adapter.get_endpoint(&["c"]).wait().unwrap();
+        assert_eq!(ep.as_str(), "http://host.one:8080/v1/c");
+    }
+
+    fn fake_object_storage_info() -> ServiceInfo {
+        ServiceInfo {
+            root_url: Url::parse("http://fake:8080/v1/").unwrap(),
+            major_version: None,
+            minimum_version: None,
+            current_version: None,
+        }
+    }
+
+    #[test]
+    fn test_invalidate_service_info() {
+        let mut s = new_regional_session();
+        s.cache_fake_service("object-store", fake_object_storage_info());
+        let s2 = s.clone();
+        let ep = s2.get_endpoint(OBJECT_STORAGE, &["c"]).wait().unwrap();
+        assert_eq!(ep.as_str(), "http://fake:8080/v1/c");
+
+        s.invalidate_service_info(FAKE);
+        let ep = s2.get_endpoint(OBJECT_STORAGE, &["c"]).wait().unwrap();
+        assert_eq!(ep.as_str(), "http://fake:8080/v1/c");
+
+        s.invalidate_service_info(OBJECT_STORAGE);
+        let ep = s2.get_endpoint(OBJECT_STORAGE, &["c"]).wait().unwrap();
+        assert_eq!(ep.as_str(), "http://host.one:8080/v1/c");
+    }
+
+    #[test]
+    fn test_service_info_ttl() {
+        let mut s = new_regional_session().with_service_info_ttl(Some(Duration::from_secs(60)));
+        assert_eq!(s.service_info_ttl(), Some(Duration::from_secs(60)));
+        s.cache_fake_service("object-store", fake_object_storage_info());
+        let ep = s.get_endpoint(OBJECT_STORAGE, &["c"]).wait().unwrap();
+        assert_eq!(ep.as_str(), "http://fake:8080/v1/c");
+
+        s.set_service_info_ttl(Some(Duration::from_secs(0)));
+        let ep = s.get_endpoint(OBJECT_STORAGE, &["c"]).wait().unwrap();
+        assert_eq!(ep.as_str(), "http://host.one:8080/v1/c");
+    }
+
+    #[test]
+    fn test_service_options_alias() {
+        let options = ServiceOptions::new().with_region_name("RegionTwo");
+        let s = new_simple_session(URL).with_service_options("volumev3", options.clone());
+        assert_eq!(s.service_options("block-storage"), Some(&options));
+        assert_eq!(s.service_options("volume"), Some(&options));
+        assert!(s.service_options("compute").is_none());
+    }
+
+    #[test]
+    fn test_adapter_default_api_version() {
+        let options = ServiceOptions::new().with_default_api_version(Some(ApiVersion(1, 58)));
+        let s = new_simple_session(URL).with_service_options("baremetal", options);
+        assert_eq!(
+            s.adapter(BAREMETAL).default_api_version(),
+            Some(ApiVersion(1, 58))
+        );
+        assert_eq!(s.adapter(FAKE).default_api_version(), None);
+    }
+
     pub const MAJOR_VERSION: ApiVersion = ApiVersion(2, 0);
 
     #[test]
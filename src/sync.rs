@@ -18,6 +18,7 @@
 
 use std::cell::RefCell;
 use std::io;
+use std::time::Duration;
 
 use futures::stream::{Stream, StreamFuture};
 use futures::{Async, Future, Poll};
@@ -27,9 +28,12 @@ use serde::de::DeserializeOwned;
 use serde::Serialize;
 use tokio::runtime::current_thread::Runtime;
 
+use super::catalog::Catalog;
 use super::request;
 use super::services::ServiceType;
-use super::{ApiVersion, AuthType, Error, Session};
+use super::{
+    ApiVersion, AuthType, EndpointFilter, Error, ProxyConfig, RewriteRules, Session, TlsConfig,
+};
 
 /// A result of an OpenStack operation.
 pub type Result<T> = ::std::result::Result<T, Error>;
@@ -99,6 +103,36 @@ impl SyncSession {
         &self.inner.endpoint_interface()
     }
 
+    /// Endpoint filter in use.
+    #[inline]
+    pub fn endpoint_filter(&self) -> &EndpointFilter {
+        self.inner.endpoint_filter()
+    }
+
+    /// Region in use (if any).
+    #[inline]
+    pub fn region(&self) -> Option<String> {
+        self.inner.region()
+    }
+
+    /// Rules used to rewrite endpoint URLs.
+    #[inline]
+    pub fn rewrite_rules(&self) -> &RewriteRules {
+        self.inner.rewrite_rules()
+    }
+
+    /// Time before a failed endpoint is tried again.
+    #[inline]
+    pub fn failover_cooldown(&self) -> Duration {
+        self.inner.failover_cooldown()
+    }
+
+    /// Time after which cached service information expires (if any).
+    #[inline]
+    pub fn service_info_ttl(&self) -> Option<Duration> {
+        self.inner.service_info_ttl()
+    }
+
     /// Refresh the session.
     #[inline]
     pub fn refresh(&mut self) -> Result<()> {
@@ -112,6 +146,15 @@ impl SyncSession {
         &self.inner
     }
 
+    /// Drop the cached information about the service.
+    ///
+    /// See [Session::invalidate_service_info](../struct.Session.html#method.invalidate_service_info)
+    /// for details.
+    #[inline]
+    pub fn invalidate_service_info<Srv: ServiceType>(&self, service: Srv) {
+        self.inner.invalidate_service_info(service);
+    }
+
     /// Set a new authentication for this `Session`.
     ///
     /// This call clears the cached service information for this `Session`.
@@ -121,10 +164,28 @@ impl SyncSession {
         self.inner.set_auth_type(auth_type);
     }
 
+    /// Use the given proxy options for authentication and service requests.
+    ///
+    /// See [Session::set_proxy](../struct.Session.html#method.set_proxy) for details.
+    #[inline]
+    pub fn set_proxy(&mut self, proxy: ProxyConfig) -> Result<()> {
+        self.inner.set_proxy(proxy)
+    }
+
+    /// Use the given TLS options for authentication and service requests.
+    ///
+    /// See [Session::set_tls_config](../struct.Session.html#method.set_tls_config) for details.
+    #[inline]
+    pub fn set_tls_config(&mut self, tls: TlsConfig) -> Result<()> {
+        self.inner.set_tls_config(tls)
+    }
+
     /// Set endpoint interface to use.
     ///
-    /// This call clears the cached service information for this `Session`.
-    /// It does not, however, affect clones of this `Session`.
+    /// The interface takes precedence over the one from the service options, e.g. from the
+    /// `<service>_interface` key in `clouds.yaml`.
+    ///
+    /// Service information is cached per interface, so this call does not clear the cache.
     #[inline]
     pub fn set_endpoint_interface<S>(&mut self, endpoint_interface: S)
     where
@@ -133,6 +194,47 @@ impl SyncSession {
         self.inner.set_endpoint_interface(endpoint_interface);
     }
 
+    /// Set the filter used to select endpoints from the service catalog.
+    ///
+    /// This call clears the cached service information for this `Session`.
+    /// It does not, however, affect clones of this `Session`.
+    #[inline]
+    pub fn set_endpoint_filter(&mut self, filter: EndpointFilter) {
+        self.inner.set_endpoint_filter(filter);
+    }
+
+    /// Set the region to use.
+    ///
+    /// See [Session::set_region](../struct.Session.html#method.set_region) for details.
+    #[inline]
+    pub fn set_region<S>(&mut self, region: S)
+    where
+        S: Into<String>,
+    {
+        self.inner.set_region(region);
+    }
+
+    /// Set the rules used to rewrite endpoint URLs.
+    ///
+    /// This call clears the cached service information for this `Session`.
+    /// It does not, however, affect clones of this `Session`.
+    #[inline]
+    pub fn set_rewrite_rules(&mut self, rules: RewriteRules) {
+        self.inner.set_rewrite_rules(rules);
+    }
+
+    /// Set the time before a failed endpoint is tried again.
+    #[inline]
+    pub fn set_failover_cooldown(&mut self, cooldown: Duration) {
+        self.inner.set_failover_cooldown(cooldown);
+    }
+
+    /// Set the time after which cached service information expires.
+    #[inline]
+    pub fn set_service_info_ttl(&mut self, ttl: Option<Duration>) {
+        self.inner.set_service_info_ttl(ttl);
+    }
+
     /// Convert this session into one using the given authentication.
     #[inline]
     pub fn with_auth_type<Auth: AuthType + 'static>(mut self, auth_method: Auth) -> SyncSession {
@@ -140,6 +242,20 @@ impl SyncSession {
         self
     }
 
+    /// Convert this session into one using the given proxy options.
+    #[inline]
+    pub fn with_proxy(mut self, proxy: ProxyConfig) -> Result<SyncSession> {
+        self.set_proxy(proxy)?;
+        Ok(self)
+    }
+
+    /// Convert this session into one using the given TLS options.
+    #[inline]
+    pub fn with_tls_config(mut self, tls: TlsConfig) -> Result<SyncSession> {
+        self.set_tls_config(tls)?;
+        Ok(self)
+    }
+
     /// Convert this session into one using the given endpoint interface.
     #[inline]
     pub fn with_endpoint_interface<S>(mut self, endpoint_interface: S) -> SyncSession
@@ -150,6 +266,88 @@ impl SyncSession {
         self
     }
 
+    /// Convert this session into one using the given endpoint filter.
+    #[inline]
+    pub fn with_endpoint_filter(mut self, filter: EndpointFilter) -> SyncSession {
+        self.set_endpoint_filter(filter);
+        self
+    }
+
+    /// Convert this session into one using the given region.
+    #[inline]
+    pub fn with_region<S>(mut self, region: S) -> SyncSession
+    where
+        S: Into<String>,
+    {
+        self.set_region(region);
+        self
+    }
+
+    /// Create a copy of this session using the given region.
+    ///
+    /// The copy shares the authentication and the cached information with this session.
+    #[inline]
+    pub fn in_region<S>(&self, region: S) -> SyncSession
+    where
+        S: Into<String>,
+    {
+        SyncSession::new(self.inner.in_region(region))
+    }
+
+    /// Convert this session into one using the given rules to rewrite endpoint URLs.
+    #[inline]
+    pub fn with_rewrite_rules(mut self, rules: RewriteRules) -> SyncSession {
+        self.set_rewrite_rules(rules);
+        self
+    }
+
+    /// Convert this session into one using the given failover cool-down.
+    #[inline]
+    pub fn with_failover_cooldown(mut self, cooldown: Duration) -> SyncSession {
+        self.set_failover_cooldown(cooldown);
+        self
+    }
+
+    /// Convert this session into one using the given time-to-live of cached service information.
+    #[inline]
+    pub fn with_service_info_ttl(mut self, ttl: Option<Duration>) -> SyncSession {
+        self.set_service_info_ttl(ttl);
+        self
+    }
+
+    /// Get the service catalog.
+    ///
+    /// The catalog is empty for authentication types without one.
+    #[inline]
+    pub fn catalog(&self) -> Result<Catalog> {
+        self.block_on(self.inner.catalog())
+    }
+
+    /// Run the same call in every region of the service.
+    ///
+    /// The regions are taken from the service catalog, the call receives a copy of this session
+    /// for each of them. Calls run one after another, their results are returned per region,
+    /// sorted by the region name.
+    pub fn for_each_region<Srv, F, T>(
+        &self,
+        service: Srv,
+        mut func: F,
+    ) -> Result<Vec<(String, Result<T>)>>
+    where
+        Srv: ServiceType,
+        F: FnMut(&SyncSession) -> Result<T>,
+    {
+        let catalog = self.catalog()?;
+        Ok(catalog
+            .regions_of(service.catalog_type())
+            .into_iter()
+            .map(|region| {
+                let session = self.in_region(region);
+                (region.to_string(), func(&session))
+            })
+            .collect())
+    }
+
     /// Get minimum/maximum API (micro)version information.
     ///
     /// Returns `None` if the range cannot be determined, which usually means
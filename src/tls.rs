@@ -0,0 +1,383 @@
+// Copyright 2019 Dmitry Tantsur <divius.inside@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! TLS configuration for HTTP clients.
+
+use std::fmt::{self, Debug};
+use std::fs;
+use std::path::Path;
+
+use reqwest::r#async::{Client, ClientBuilder};
+use reqwest::Certificate;
+use zeroize::Zeroizing;
+
+use super::{Error, ErrorKind};
+
+/// TLS options for the HTTP client used for authentication and service requests.
+///
+/// Build the options and create a client to pass to an authentication type:
+///
+/// ```rust,no_run
+/// let client = osauth::TlsConfig::new()
+///     .with_ca_certificate_file("/etc/ssl/private-ca.pem")
+///     .expect("Cannot read the CA certificate")
+///     .build_client()
+///     .expect("Cannot create an HTTP client");
+///
+/// let auth = osauth::NoAuth::new_with_client("https://cloud.local/baremetal", client)
+///     .expect("Invalid endpoint URL");
+/// let session = osauth::Session::new(auth);
+/// ```
+///
+/// Client certificates in the PEM format require the `rustls` feature, the `native-tls` feature
+/// only supports them in the PKCS #12 format.
+#[derive(Clone)]
+pub struct TlsConfig {
+    ca_certificates: Vec<Vec<u8>>,
+    client_certificate: Option<ClientCertificate>,
+    verify: bool,
+}
+
+#[derive(Clone)]
+enum ClientCertificate {
+    Pem {
+        cert: Vec<u8>,
+        key: Zeroizing<Vec<u8>>,
+    },
+    #[cfg(feature = "native-tls")]
+    Pkcs12 {
+        der: Zeroizing<Vec<u8>>,
+        password: Zeroizing<String>,
+    },
+}
+
+impl Debug for TlsConfig {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("TlsConfig")
+            .field("ca_certificates", &self.ca_certificates.len())
+            .field(
+                "client_certificate",
+                &self.client_certificate.as_ref().map(|_| "***"),
+            )
+            .field("verify", &self.verify)
+            .finish()
+    }
+}
+
+impl Default for TlsConfig {
+    fn default() -> TlsConfig {
+        TlsConfig::new()
+    }
+}
+
+fn read_file(path: &Path, what: &str) -> Result<Vec<u8>, Error> {
+    fs::read(path).map_err(|e| {
+        Error::new(
+            ErrorKind::InvalidInput,
+            format!("Cannot read {} from {}: {}", what, path.display(), e),
+        )
+    })
+}
+
+impl TlsConfig {
+    /// Create default TLS options: system CA certificates and verification enabled.
+    pub fn new() -> TlsConfig {
+        TlsConfig {
+            ca_certificates: Vec::new(),
+            client_certificate: None,
+            verify: true,
+        }
+    }
+
+    /// Whether TLS certificates are verified.
+    #[inline]
+    pub fn verify(&self) -> bool {
+        self.verify
+    }
+
+    /// Whether a client certificate is set.
+    #[inline]
+    pub fn has_client_certificate(&self) -> bool {
+        self.client_certificate.is_some()
+    }
+
+    /// Add a trusted CA certificate in the PEM format.
+    ///
+    /// Several concatenated certificates (a bundle) are accepted.
+    #[inline]
+    pub fn add_ca_certificate<B: Into<Vec<u8>>>(&mut self, pem: B) {
+        self.ca_certificates.push(pem.into());
+    }
+
+    /// Add trusted CA certificate(s) from a PEM file.
+    ///
+    /// A file with several certificates (a bundle) adds all of them.
+    pub fn add_ca_certificate_file<P: AsRef<Path>>(&mut self, path: P) -> Result<(), Error> {
+        let pem = read_file(path.as_ref(), "CA certificate")?;
+        self.add_ca_certificate(pem);
+        Ok(())
+    }
+
+    /// Set a client certificate and its private key in the PEM format.
+    #[inline]
+    pub fn set_client_certificate<B1, B2>(&mut self, cert: B1, key: B2)
+    where
+        B1: Into<Vec<u8>>,
+        B2: Into<Vec<u8>>,
+    {
+        self.client_certificate = Some(ClientCertificate::Pem {
+            cert: cert.into(),
+            key: Zeroizing::new(key.into()),
+        });
+    }
+
+    /// Set a client certificate and its private key from PEM files.
+    pub fn set_client_certificate_files<P1, P2>(&mut self, cert: P1, key: P2) -> Result<(), Error>
+    where
+        P1: AsRef<Path>,
+        P2: AsRef<Path>,
+    {
+        let cert = read_file(cert.as_ref(), "client certificate")?;
+        let key = read_file(key.as_ref(), "client key")?;
+        self.set_client_certificate(cert, key);
+        Ok(())
+    }
+
+    /// Set a client certificate with its private key as a PKCS #12 archive.
+    #[cfg(feature = "native-tls")]
+    #[inline]
+    pub fn set_client_pkcs12<B, S>(&mut self, der: B, password: S)
+    where
+        B: Into<Vec<u8>>,
+        S: Into<String>,
+    {
+        self.client_certificate = Some(ClientCertificate::Pkcs12 {
+            der: Zeroizing::new(der.into()),
+            password: Zeroizing::new(password.into()),
+        });
+    }
+
+    /// Enable or disable verification of TLS certificates.
+    ///
+    /// Disabling verification is insecure and should only be used for testing.
+    #[inline]
+    pub fn set_verify(&mut self, verify: bool) {
+        self.verify = verify;
+    }
+
+    /// Add a trusted CA certificate in the PEM format.
+    #[inline]
+    pub fn with_ca_certificate<B: Into<Vec<u8>>>(mut self, pem: B) -> Self {
+        self.add_ca_certificate(pem);
+        self
+    }
+
+    /// Add trusted CA certificate(s) from a PEM file.
+    #[inline]
+    pub fn with_ca_certificate_file<P: AsRef<Path>>(mut self, path: P) -> Result<Self, Error> {
+        self.add_ca_certificate_file(path)?;
+        Ok(self)
+    }
+
+    /// Set a client certificate and its private key in the PEM format.
+    #[inline]
+    pub fn with_client_certificate<B1, B2>(mut self, cert: B1, key: B2) -> Self
+    where
+        B1: Into<Vec<u8>>,
+        B2: Into<Vec<u8>>,
+    {
+        self.set_client_certificate(cert, key);
+        self
+    }
+
+    /// Set a client certificate and its private key from PEM files.
+    #[inline]
+    pub fn with_client_certificate_files<P1, P2>(mut self, cert: P1, key: P2) -> Result<Self, Error>
+    where
+        P1: AsRef<Path>,
+        P2: AsRef<Path>,
+    {
+        self.set_client_certificate_files(cert, key)?;
+        Ok(self)
+    }
+
+    /// Set a client certificate with its private key as a PKCS #12 archive.
+    #[cfg(feature = "native-tls")]
+    #[inline]
+    pub fn with_client_pkcs12<B, S>(mut self, der: B, password: S) -> Self
+    where
+        B: Into<Vec<u8>>,
+        S: Into<String>,
+    {
+        self.set_client_pkcs12(der, password);
+        self
+    }
+
+    /// Enable or disable verification of TLS certificates.
+    #[inline]
+    pub fn with_verify(mut self, verify: bool) -> Self {
+        self.set_verify(verify);
+        self
+    }
+
+    /// Apply these options to an HTTP client builder.
+    pub fn apply(&self, builder: ClientBuilder) -> Result<ClientBuilder, Error> {
+        #[cfg(feature = "rustls")]
+        let mut builder = builder.use_rustls_tls();
+        #[cfg(not(feature = "rustls"))]
+        let mut builder = builder;
+
+        // The native-tls backend only reads the first certificate of a bundle.
+        for pem in self.ca_certificates.iter().flat_map(|pem| split_pem(pem)) {
+            let cert = Certificate::from_pem(pem).map_err(|e| {
+                Error::new(
+                    ErrorKind::InvalidInput,
+                    format!("Invalid CA certificate: {}", e),
+                )
+            })?;
+            builder = builder.add_root_certificate(cert);
+        }
+
+        if let Some(ref client_cert) = self.client_certificate {
+            builder = builder.identity(client_cert.identity()?);
+        }
+
+        Ok(builder.danger_accept_invalid_certs(!self.verify))
+    }
+
+    /// Create an HTTP client with these options.
+    pub fn build_client(&self) -> Result<Client, Error> {
+        Ok(self.apply(Client::builder())?.build()?)
+    }
+}
+
+const PEM_END: &[u8] = b"-----END CERTIFICATE-----";
+
+/// Split a PEM bundle into separate certificates.
+///
+/// The data is returned as it is if it contains no complete certificates.
+fn split_pem(pem: &[u8]) -> Vec<&[u8]> {
+    let mut result = Vec::new();
+    let mut rest = pem;
+    while let Some(pos) = rest
+        .windows(PEM_END.len())
+        .position(|window| window == PEM_END)
+    {
+        let end = pos + PEM_END.len();
+        result.push(&rest[..end]);
+        rest = &rest[end..];
+    }
+    if result.is_empty() {
+        result.push(pem);
+    }
+    result
+}
+
+impl ClientCertificate {
+    #[cfg(feature = "rustls")]
+    fn pem_identity(cert: &[u8], key: &[u8]) -> Result<reqwest::Identity, Error> {
+        let mut buf = Zeroizing::new(key.to_vec());
+        buf.push(b'\n');
+        buf.extend_from_slice(cert);
+        reqwest::Identity::from_pem(&buf).map_err(|e| {
+            Error::new(
+                ErrorKind::InvalidInput,
+                format!("Invalid client certificate or key: {}", e),
+            )
+        })
+    }
+
+    #[cfg(not(feature = "rustls"))]
+    fn pem_identity(_cert: &[u8], _key: &[u8]) -> Result<reqwest::Identity, Error> {
+        Err(Error::new(
+            ErrorKind::InvalidInput,
+            "Client certificates in the PEM format require the rustls feature",
+        ))
+    }
+
+    fn identity(&self) -> Result<reqwest::Identity, Error> {
+        match self {
+            ClientCertificate::Pem { cert, key } => ClientCertificate::pem_identity(cert, key),
+            #[cfg(feature = "native-tls")]
+            ClientCertificate::Pkcs12 { der, password } => {
+                reqwest::Identity::from_pkcs12_der(der, password).map_err(|e| {
+                    Error::new(
+                        ErrorKind::InvalidInput,
+                        format!("Invalid client certificate archive: {}", e),
+                    )
+                })
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::ErrorKind;
+    use super::{split_pem, TlsConfig};
+
+    #[test]
+    fn test_tls_config_default() {
+        let tls = TlsConfig::new();
+        assert!(tls.verify());
+        assert!(!tls.has_client_certificate());
+        let _ = tls.build_client().unwrap();
+    }
+
+    #[test]
+    fn test_tls_config_insecure() {
+        let tls = TlsConfig::new().with_verify(false);
+        assert!(!tls.verify());
+        let _ = tls.build_client().unwrap();
+    }
+
+    #[test]
+    fn test_tls_config_invalid() {
+        let err = TlsConfig::new()
+            .with_ca_certificate("not a certificate")
+            .build_client()
+            .err()
+            .unwrap();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+
+        let err = TlsConfig::new()
+            .with_ca_certificate_file("/nonexistent/ca.pem")
+            .err()
+            .unwrap();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn test_split_pem() {
+        let one = "-----BEGIN CERTIFICATE-----\nAAAA\n-----END CERTIFICATE-----";
+        let two = "\n-----BEGIN CERTIFICATE-----\nBBBB\n-----END CERTIFICATE-----";
+        let bundle = format!("{}{}\n", one, two);
+        assert_eq!(
+            split_pem(bundle.as_bytes()),
+            vec![one.as_bytes(), two.as_bytes()]
+        );
+        assert_eq!(
+            split_pem(b"not a certificate"),
+            vec![&b"not a certificate"[..]]
+        );
+    }
+
+    #[test]
+    fn test_tls_config_debug() {
+        let tls = TlsConfig::new().with_client_certificate("cert", "secret key");
+        assert!(tls.has_client_certificate());
+        let debug = format!("{:?}", tls);
+        assert!(!debug.contains("secret key"));
+    }
+}